@@ -1,7 +1,7 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use term_grid::{Direction, Filling, Grid, GridOptions};
+use term_grid::{Direction, Filling, Grid, GridOptions, Width};
 
 // This produces:
 //
@@ -21,7 +21,8 @@ fn main() {
         GridOptions {
             direction: Direction::TopToBottom,
             filling: Filling::Text(" | ".into()),
-            width: 80,
+            width: Width::Columns(80),
+            ..Default::default()
         },
     );
 
@@ -1,14 +1,16 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use term_grid::{Direction, Filling, Grid, GridOptions};
+use std::io::{self, BufWriter};
+use term_grid::{Direction, DisplayCell, Filling, Grid, GridOptions, Width};
 
-fn main() {
+fn main() -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
     let mut n: u64 = 1234;
     for _ in 0..50 {
         let mut cells = Vec::new();
         for _ in 0..10000 {
-            cells.push(n.to_string());
+            cells.push(DisplayCell::new(n));
             n = n.overflowing_pow(2).0 % 100000000;
         }
 
@@ -17,10 +19,13 @@ fn main() {
             GridOptions {
                 direction: Direction::TopToBottom,
                 filling: Filling::Text(" | ".into()),
-                width: 80,
+                width: Width::Columns(80),
+                ..Default::default()
             },
         );
 
-        println!("{grid}");
+        grid.write_to(&mut out)?;
     }
+
+    Ok(())
 }
@@ -0,0 +1,34 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Keeping right-to-left text within its column on terminals that reorder
+//! bidirectional text.
+
+use alloc::borrow::Cow;
+use alloc::format;
+use unicode_bidi::{bidi_class, BidiClass};
+
+/// FIRST STRONG ISOLATE, which starts an isolated run of text whose direction
+/// is taken from its first strong character.
+const FSI: char = '\u{2068}';
+
+/// POP DIRECTIONAL ISOLATE, which ends the run started by [`FSI`].
+const PDI: char = '\u{2069}';
+
+/// Wraps the contents of a cell in a directional isolate if they contain
+/// any right-to-left text.
+///
+/// Without the isolate, a terminal that applies the bidirectional algorithm
+/// can move neutral characters such as spaces and punctuation across the
+/// boundary between cells, so the columns no longer line up. The isolate
+/// characters have no width, so the measured widths stay correct.
+pub(crate) fn isolate(contents: &str) -> Cow<'_, str> {
+    let rtl = contents
+        .chars()
+        .any(|c| matches!(bidi_class(c), BidiClass::R | BidiClass::AL));
+    if rtl {
+        Cow::Owned(format!("{FSI}{contents}{PDI}"))
+    } else {
+        Cow::Borrowed(contents)
+    }
+}
@@ -0,0 +1,70 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Laying out enormous inputs a chunk at a time.
+
+use crate::widths::CellWidths;
+use crate::{div_ceil, Cell, Direction, Grid, GridOptions, RenderPlan};
+use std::io;
+
+impl<T: Cell> Grid<T> {
+    /// Lays out and writes the cells `chunk_size` at a time, so that no more
+    /// than one chunk of cells is held in memory at once.
+    ///
+    /// The first chunk is laid out like a grid of its own, and its columns
+    /// serve as a sample for the rest: every later chunk is written with the
+    /// same number of columns of the same widths, so the columns line up
+    /// from one chunk to the next. A cell in a later chunk that is wider than
+    /// its column is truncated. Everything else that depends on all of the
+    /// cells, such as the line numbers, applies to each chunk on its own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn write_chunked<W: io::Write>(
+        cells: impl IntoIterator<Item = T>,
+        options: GridOptions,
+        chunk_size: usize,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        assert!(chunk_size > 0, "chunk size must be greater than zero");
+        let mut cells = cells.into_iter();
+
+        let sample: Vec<T> = cells.by_ref().take(chunk_size).collect();
+        if sample.is_empty() {
+            return Ok(());
+        }
+        let mut grid = Self::new(sample, options);
+        grid.write_to(writer)?;
+        let sample_widths = grid.layout.widths.clone();
+
+        loop {
+            let chunk: Vec<T> = cells.by_ref().take(chunk_size).collect();
+            if chunk.is_empty() {
+                return Ok(());
+            }
+            if !grid.options.final_line_ending && grid.layout.num_lines > 0 {
+                writer.write_all(grid.options.line_ending.as_str().as_bytes())?;
+            }
+
+            let widths: Vec<usize> = chunk.iter().map(|c| grid.options.measure(c)).collect();
+            grid.cells = chunk;
+            grid.widths = CellWidths::new(widths);
+            grid.widest_cell_width = grid.widths.max();
+
+            let num_columns = sample_widths.len().min(grid.cells.len());
+            let num_lines = div_ceil(grid.cells.len(), num_columns);
+            let num_columns = match grid.options.direction {
+                Direction::TopToBottom | Direction::BottomToTop => {
+                    div_ceil(grid.cells.len(), num_lines)
+                }
+                _ => num_columns,
+            };
+            let mut layout = grid.compute_dimensions(&grid.widths, num_lines, num_columns);
+            layout.widths.copy_from_slice(&sample_widths[..num_columns]);
+            grid.layout = layout;
+            grid.plan = RenderPlan::new(&grid);
+            grid.write_to(writer)?;
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Writing a grid as `crossterm` commands, so that the cells are styled by
+//! `crossterm` instead of by escape sequences in their contents.
+
+use crate::{Cell, Grid, RowWriter};
+use core::fmt;
+use crossterm::style::{ContentStyle, Print, PrintStyledContent};
+use crossterm::QueueableCommand;
+use std::io;
+
+impl<T: Cell, M> Grid<T, M> {
+    /// Queues the grid on `writer` as `crossterm` commands, printing the
+    /// contents of each cell with the style that `style` returns for it.
+    ///
+    /// The style function is called with the index of the cell and the
+    /// contents that are printed, which is only part of the cell if it was
+    /// truncated or wrapped. Escape sequences in the cells, the zebra stripes
+    /// and the highlighting of selected cells are left out, as with
+    /// [`GridOptions::strip_ansi`](crate::GridOptions::strip_ansi), so that
+    /// the styles only come from `crossterm`. The padding, separators and
+    /// line endings are printed without a style.
+    ///
+    /// The commands are only queued, so the writer still needs to be flushed
+    /// to show the grid.
+    pub fn write_crossterm<W: io::Write>(
+        &self,
+        writer: &mut W,
+        style: impl FnMut(usize, &str) -> ContentStyle,
+    ) -> io::Result<()> {
+        let mut commands = Commands {
+            writer,
+            style,
+            error: None,
+        };
+        self.write_unstyled(&mut commands).map_err(|fmt::Error| {
+            (commands.error.take())
+                .unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "formatter error"))
+        })
+    }
+}
+
+/// Turns the rows that a grid writes into `crossterm` commands.
+struct Commands<'a, W, F> {
+    writer: &'a mut W,
+    style: F,
+    /// The error that the writer returned, which `fmt::Write` can't pass on.
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write, F> Commands<'_, W, F> {
+    /// Keeps the error of a command to return it later.
+    fn keep_error(&mut self, result: io::Result<()>) -> fmt::Result {
+        result.map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+impl<W: io::Write, F> fmt::Write for Commands<'_, W, F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let result = self.writer.queue(Print(s)).map(|_| ());
+        self.keep_error(result)
+    }
+}
+
+impl<W: io::Write, F: FnMut(usize, &str) -> ContentStyle> RowWriter for Commands<'_, W, F> {
+    fn write_cell_contents(&mut self, num: usize, contents: &str) -> fmt::Result {
+        let styled = (self.style)(num, contents).apply(contents);
+        let result = self.writer.queue(PrintStyledContent(styled)).map(|_| ());
+        self.keep_error(result)
+    }
+}
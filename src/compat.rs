@@ -0,0 +1,135 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Reproducing the layouts of other programs exactly, for
+//! [`Compat`](crate::Compat).
+
+use crate::widths::ColumnWidths;
+use crate::{div_ceil, Cell, Direction, Grid, Layout};
+use alloc::vec::Vec;
+
+impl<T: Cell, M> Grid<T, M> {
+    /// Chooses the number of columns the way `calculate_columns` in GNU
+    /// `ls.c` does.
+    ///
+    /// Every column starts out as wide as a single character and its
+    /// separator, and grows to fit its widest cell plus the separator, except
+    /// for the last column, which has no separator. A number of columns is
+    /// only accepted while the line is strictly narrower than the available
+    /// width, and the most columns that are accepted win.
+    pub(crate) fn gnu_ls_layout(&self) -> Layout {
+        let widths = self.layout_widths();
+        let num_cells = widths.len();
+        if num_cells == 0 {
+            return self.layout_with(0, ColumnWidths::default());
+        }
+
+        let filling = &self.options.filling;
+        let line_length = self.available_width;
+        let min_column_width = 1 + filling.gap_width(0);
+        let max_columns = div_ceil(line_length, min_column_width).clamp(1, num_cells);
+        let by_columns = matches!(
+            self.options.direction,
+            Direction::TopToBottom | Direction::BottomToTop
+        );
+
+        let mut candidates: Vec<Candidate> = (1..=max_columns)
+            .map(|num_columns| Candidate::new(num_columns, min_column_width))
+            .collect();
+        for (index, width) in widths.iter().enumerate() {
+            let position = if self.options.reverse {
+                num_cells - 1 - index
+            } else {
+                index
+            };
+            for candidate in candidates.iter_mut().filter(|c| c.valid) {
+                let num_columns = candidate.column_widths.len();
+                let column = if by_columns {
+                    position / div_ceil(num_cells, num_columns)
+                } else {
+                    position % num_columns
+                };
+                let gap = if column + 1 == num_columns {
+                    0
+                } else {
+                    filling.gap_width(column)
+                };
+                candidate.fit(column, width + gap, line_length);
+            }
+        }
+
+        let num_columns = (1..=max_columns)
+            .rev()
+            .find(|&num_columns| candidates[num_columns - 1].valid)
+            .unwrap_or(1);
+        let num_lines = div_ceil(num_cells, num_columns);
+        let num_columns = if by_columns {
+            div_ceil(num_cells, num_lines)
+        } else {
+            num_columns
+        };
+        self.compute_dimensions(&widths, num_lines, num_columns)
+    }
+
+    /// Chooses the number of columns the way `printcol` in BSD `ls` does.
+    ///
+    /// Every column is as wide as the widest cell plus its separator,
+    /// rounded up to the next tab stop, and the line is filled with as many
+    /// of those as fit, including the last one.
+    pub(crate) fn bsd_ls_layout(&self) -> Layout {
+        let widths = self.layout_widths();
+        let num_cells = widths.len();
+        let Some(widest) = widths.iter().max() else {
+            return self.layout_with(0, ColumnWidths::default());
+        };
+
+        let gap = self.options.filling.gap_width(0);
+        let column_width = div_ceil(widest + gap, BSD_TAB_WIDTH) * BSD_TAB_WIDTH;
+        let num_columns = (self.available_width / column_width).clamp(1, num_cells);
+        let num_lines = div_ceil(num_cells, num_columns);
+        let num_columns = match self.options.direction {
+            Direction::TopToBottom | Direction::BottomToTop => div_ceil(num_cells, num_lines),
+            _ => num_columns,
+        };
+
+        let mut layout = self.compute_dimensions(&widths, num_lines, num_columns);
+        layout.widths = ColumnWidths::filled(column_width - gap, num_columns);
+        layout
+    }
+}
+
+/// The distance between tab stops that BSD `ls` lines its columns up with.
+const BSD_TAB_WIDTH: usize = 8;
+
+/// The columns of one possible number of columns, while GNU `ls` is
+/// measuring them.
+struct Candidate {
+    /// Whether the line is still narrower than the available width.
+    valid: bool,
+
+    /// The width of the whole line.
+    line_length: usize,
+
+    /// The width of each column, including its separator.
+    column_widths: ColumnWidths,
+}
+
+impl Candidate {
+    fn new(num_columns: usize, min_column_width: usize) -> Self {
+        Self {
+            valid: true,
+            line_length: num_columns * min_column_width,
+            column_widths: ColumnWidths::filled(min_column_width, num_columns),
+        }
+    }
+
+    /// Widens a column to fit a cell, giving up once the line has become
+    /// too long.
+    fn fit(&mut self, column: usize, width: usize, available_width: usize) {
+        if self.column_widths[column] < width {
+            self.line_length += width - self.column_widths[column];
+            self.column_widths[column] = width;
+            self.valid = self.line_length < available_width;
+        }
+    }
+}
@@ -0,0 +1,26 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! The calls into the C library, which are the only unsafe code in the
+//! crate.
+//!
+//! Everything else denies unsafe code, so this module is where to look when
+//! auditing it. It only exists with the `locale` feature, which is also the
+//! only feature that depends on `libc`.
+
+use core::cmp::Ordering;
+use std::ffi::CStr;
+
+/// Compares two strings with the C library's `strcoll`.
+///
+/// `strcoll` orders the strings by the `LC_COLLATE` category of the locale
+/// that the process last selected with `setlocale`. That is global state
+/// of the process, which this crate never changes: until the program calls
+/// `setlocale` itself, the C library stays in the `C` locale, which compares
+/// bytes.
+pub(crate) fn strcoll(a: &CStr, b: &CStr) -> Ordering {
+    // SAFETY: both pointers point to nul-terminated strings that live until
+    // the end of the call, and `strcoll` only reads them.
+    let result = unsafe { libc::strcoll(a.as_ptr(), b.as_ptr()) };
+    result.cmp(&0)
+}
@@ -0,0 +1,70 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Serialization of the computed layout, so that front-ends can reproduce the
+//! grid without redoing the layout themselves.
+
+use crate::{Cell, Grid};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// A single cell along with the position it was assigned in the layout.
+#[derive(serde::Serialize)]
+struct PlacedCell<'a> {
+    /// The index of the cell in the grid, after any sorting.
+    index: usize,
+    /// The index the cell had in the `Vec` the grid was created from, before
+    /// any sorting. See [`Grid::original_index`].
+    original_index: usize,
+    row: usize,
+    column: usize,
+    width: usize,
+    contents: Cow<'a, str>,
+}
+
+/// Serializes the layout: the number of rows, the width of each column and
+/// every cell with its row, column and width.
+///
+/// Each cell has two indices: `index` is its current index in the grid, as
+/// used by methods like [`Grid::cell_position`], and `original_index` is the
+/// index it had in the `Vec` the grid was created from. They only differ once
+/// the grid has been sorted.
+impl<T: Cell, M> Serialize for Grid<T, M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let cells: Vec<PlacedCell<'_>> = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                let (row, column) = self.layout.place(index);
+                PlacedCell {
+                    index,
+                    original_index: self.original_index(index).unwrap_or(index),
+                    row,
+                    column,
+                    width: self.widths.get(index),
+                    contents: cell.text(),
+                }
+            })
+            .collect();
+
+        let mut state = serializer.serialize_struct("Grid", 3)?;
+        state.serialize_field("rows", &self.row_count())?;
+        state.serialize_field("column_widths", self.column_widths())?;
+        state.serialize_field("cells", &cells)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: Cell, M> Grid<T, M> {
+    /// Serializes the computed layout to a JSON string.
+    ///
+    /// See the [`Serialize`] implementation for the structure of the output.
+    pub fn to_json(&self) -> String {
+        // Serializing this structure to a string cannot fail: it only
+        // contains strings and integers and has no maps.
+        serde_json::to_string(self).expect("layout should serialize to JSON")
+    }
+}
@@ -1,6 +1,7 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(future_incompatible)]
 #![warn(missing_copy_implementations)]
 #![warn(missing_docs)]
@@ -10,312 +11,4404 @@
 #![deny(unsafe_code)]
 #![doc = include_str!("../README.md")]
 
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::{format, vec};
 use ansi_width::ansi_width;
-use std::fmt;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Bound, Range, RangeBounds};
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+#[cfg(feature = "bidi")]
+mod bidi;
+#[cfg(feature = "std")]
+mod chunked;
+#[cfg(feature = "crossterm")]
+mod commands;
+mod compat;
+#[cfg(feature = "locale")]
+#[allow(unsafe_code)]
+mod ffi;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "locale")]
+mod locale;
+mod maxima;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(all(feature = "resize", unix))]
+mod resize;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "terminal")]
+mod terminal;
+mod text;
+#[cfg(feature = "version-sort")]
+mod version;
+#[cfg(feature = "ratatui")]
+mod widget;
+mod widths;
+#[cfg(feature = "termcolor")]
+mod write_color;
+
+#[cfg(feature = "locale")]
+pub use locale::locale_cmp;
+use maxima::{RangeMax, SortedWidths};
+#[cfg(feature = "std")]
+pub use stream::GridStream;
+use text::{
+    cluster_width, expand_tabs, is_printable_ascii, next_tab_stop, strip_escapes, tabbed_width,
+    truncate, wrap, Measure,
+};
+#[cfg(feature = "version-sort")]
+pub use version::version_cmp;
+#[cfg(feature = "ratatui")]
+pub use widget::{GridWidget, GridWidgetState};
+use widths::{CellWidths, ColumnWidths};
+
+/// Direction cells should be written in: across, downwards or backwards.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Direction {
+    /// Starts at the top left and moves rightwards, going back to the first
+    /// column for a new row, like a typewriter.
+    LeftToRight,
+
+    /// Starts at the top left and moves downwards, going back to the first
+    /// row for a new column, like how `ls` lists files by default.
+    TopToBottom,
+
+    /// Starts at the top right and moves leftwards, going back to the last
+    /// column for a new row, for listings in right-to-left languages. A
+    /// final row that isn't full is aligned with the right edge.
+    RightToLeft,
+
+    /// Starts at the bottom left and moves upwards, going back to the last
+    /// row for a new column, so that the last cells end up at the bottom
+    /// like in a chat log.
+    BottomToTop,
+
+    /// Starts at the top left and moves rightwards, then turns around at the
+    /// end of each row, so that the rows alternate between going rightwards
+    /// and leftwards (also known as boustrophedon).
+    Snake,
+}
+
+/// Parses the names `ls` uses for its formats: `"across"` for
+/// [`Direction::LeftToRight`] and `"vertical"` for [`Direction::TopToBottom`].
+/// `"horizontal"`, `"left-to-right"`, `"down"`, `"top-to-bottom"`,
+/// `"right-to-left"`, `"up"`, `"bottom-to-top"`, `"snake"` and
+/// `"boustrophedon"` are accepted as well, ignoring ASCII case.
+impl FromStr for Direction {
+    type Err = ParseDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let matches = |names: &[&str]| names.iter().any(|name| name.eq_ignore_ascii_case(s));
+        if matches(&["across", "horizontal", "left-to-right"]) {
+            Ok(Direction::LeftToRight)
+        } else if matches(&["vertical", "down", "top-to-bottom"]) {
+            Ok(Direction::TopToBottom)
+        } else if matches(&["right-to-left"]) {
+            Ok(Direction::RightToLeft)
+        } else if matches(&["up", "bottom-to-top"]) {
+            Ok(Direction::BottomToTop)
+        } else if matches(&["snake", "boustrophedon"]) {
+            Ok(Direction::Snake)
+        } else {
+            Err(ParseDirectionError(s.into()))
+        }
+    }
+}
+
+/// Writes the names that [`Direction::from_str`] accepts: `"across"`,
+/// `"vertical"`, `"right-to-left"`, `"bottom-to-top"` or `"snake"`.
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Direction::LeftToRight => "across",
+            Direction::TopToBottom => "vertical",
+            Direction::RightToLeft => "right-to-left",
+            Direction::BottomToTop => "bottom-to-top",
+            Direction::Snake => "snake",
+        })
+    }
+}
+
+/// The error returned when a string is not a known [`Direction`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseDirectionError(String);
+
+impl fmt::Display for ParseDirectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid direction: {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseDirectionError {}
+
+/// The text to put in between each pair of columns.
+///
+/// This does not include any spaces used when aligning cells.
+#[derive(PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Filling {
+    /// A number of spaces
+    Spaces(usize),
+
+    /// An arbitrary string
+    ///
+    /// `"|"` is a common choice.
+    Text(String),
+
+    /// A separate string for each gap between two columns, starting with the
+    /// gap after the first column
+    ///
+    /// The last string is used for any gaps beyond the end of the list, so
+    /// `vec![" │ ".into(), " ".into()]` sets the first column apart and puts
+    /// single spaces between the others.
+    PerColumn(Vec<String>),
+
+    /// A string that takes up the given number of columns, regardless of
+    /// what it contains
+    ///
+    /// This is meant for separators with escape sequences, such as a dimmed
+    /// `"│"`, whose width should not be measured. If the text changes the
+    /// style without resetting it, a reset is added after it so that the
+    /// style doesn't leak into the next cell.
+    Styled {
+        /// The separator, including any escape sequences.
+        text: String,
+
+        /// The number of columns the separator takes up.
+        width: usize,
+    },
+
+    /// A single tab between cells, without any padding, for elastic
+    /// tabstops
+    ///
+    /// With elastic tabstops, whatever displays the output lines the columns
+    /// up by itself, putting at least the given number of columns between
+    /// them. The grid is laid out with that gap so that it still fits the
+    /// width.
+    ElasticTabs(usize),
+
+    /// A number of columns between the cells, written together with the
+    /// padding before them as tabs and spaces, like `ls -T`
+    ///
+    /// A tab is written wherever it reaches a tab stop without going past
+    /// where the next column starts, counting from the start of the line,
+    /// and spaces are written for the rest. This is the same as what GNU
+    /// `ls` writes for `--tabsize`, so a tab size of 0 writes only spaces.
+    ///
+    /// The tab stops are the columns in `stops`, followed by every multiple
+    /// of `tab_size` past the last of them. Without any `stops`, they are
+    /// every `tab_size` columns, as in most terminals.
+    Tabs {
+        /// The number of columns between the cells.
+        spaces: usize,
+
+        /// The number of columns between tab stops past the last of `stops`.
+        tab_size: usize,
+
+        /// The columns of the first tab stops, counting from 0, in
+        /// increasing order. A stop that is not past the one before it is
+        /// ignored.
+        #[cfg_attr(feature = "serde", serde(default))]
+        stops: Vec<usize>,
+    },
+
+    /// A separator computed for each gap between two columns from the index
+    /// of the column before it
+    ///
+    /// The function is called while laying out the grid as well as when
+    /// rendering it, so it should be cheap and always return the same
+    /// separator for the same column.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Dynamic(SeparatorFn),
+}
+
+/// A function that returns the separator to put after the column with the
+/// given index, for [`Filling::Dynamic`].
+///
+/// Two of these are equal only if they are clones of each other.
+#[derive(Clone)]
+pub struct SeparatorFn(Arc<dyn Fn(usize) -> String + Send + Sync>);
+
+impl SeparatorFn {
+    /// Wraps a function to compute separators with.
+    pub fn new(f: impl Fn(usize) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// The separator to put after the column with the given index.
+    pub fn separator(&self, column: usize) -> String {
+        (self.0)(column)
+    }
+}
+
+impl PartialEq for SeparatorFn {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SeparatorFn {}
+
+impl fmt::Debug for SeparatorFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SeparatorFn(..)")
+    }
+}
+
+impl Filling {
+    /// The separator to put after column `gap`.
+    fn separator(&self, gap: usize) -> Cow<'_, str> {
+        match self {
+            Filling::Spaces(n) | Filling::Tabs { spaces: n, .. } => Cow::Owned(" ".repeat(*n)),
+            Filling::Text(t) => Cow::Borrowed(t),
+            Filling::Styled { text, .. } => {
+                if text.contains("\x1b[") && !text.ends_with("\x1b[0m") && !text.ends_with("\x1b[m")
+                {
+                    Cow::Owned(format!("{text}\x1b[0m"))
+                } else {
+                    Cow::Borrowed(text)
+                }
+            }
+            Filling::PerColumn(separators) => {
+                let separator = separators.get(gap).or(separators.last());
+                Cow::Borrowed(separator.map_or("", String::as_str))
+            }
+            Filling::ElasticTabs(_) => Cow::Borrowed("\t"),
+            Filling::Dynamic(f) => Cow::Owned(f.separator(gap)),
+        }
+    }
+
+    /// The width of the separator after column `gap`.
+    fn gap_width(&self, gap: usize) -> usize {
+        match self {
+            Filling::Spaces(w)
+            | Filling::ElasticTabs(w)
+            | Filling::Tabs { spaces: w, .. }
+            | Filling::Styled { width: w, .. } => *w,
+            _ => ansi_width(&self.separator(gap)),
+        }
+    }
+
+    /// The combined width of the separators between `num_columns` columns.
+    fn separators_width(&self, num_columns: usize) -> usize {
+        match self {
+            Filling::PerColumn(_) | Filling::Dynamic(_) => (0..num_columns.saturating_sub(1))
+                .map(|gap| self.gap_width(gap))
+                .sum(),
+            _ => self.gap_width(0) * num_columns.saturating_sub(1),
+        }
+    }
+}
+
+/// Parses a filling from the separator itself: a string of only spaces
+/// becomes [`Filling::Spaces`] and anything else becomes [`Filling::Text`].
+///
+/// The exception is `"tabs:SPACES:TAB_SIZE"`, such as `"tabs:2:8"`, which
+/// becomes [`Filling::Tabs`] with the given numbers. It can be followed by
+/// a comma-separated list of tab stops, as in `"tabs:2:8:4,6,12"`.
+impl FromStr for Filling {
+    type Err = ParseFillingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(numbers) = s.strip_prefix("tabs:") {
+            let error = || ParseFillingError(s.into());
+            let (spaces, rest) = numbers.split_once(':').ok_or_else(error)?;
+            let (tab_size, stops) = match rest.split_once(':') {
+                Some((tab_size, stops)) => (tab_size, Some(stops)),
+                None => (rest, None),
+            };
+            let stops = match stops {
+                Some(stops) => (stops.split(','))
+                    .map(|stop| stop.parse().map_err(|_| error()))
+                    .collect::<Result<_, _>>()?,
+                None => Vec::new(),
+            };
+            return Ok(Filling::Tabs {
+                spaces: spaces.parse().map_err(|_| error())?,
+                tab_size: tab_size.parse().map_err(|_| error())?,
+                stops,
+            });
+        }
+        if s.bytes().all(|b| b == b' ') {
+            Ok(Filling::Spaces(s.len()))
+        } else {
+            Ok(Filling::Text(s.into()))
+        }
+    }
+}
+
+/// Writes the separator that the filling puts between columns, or between
+/// the first two columns for [`Filling::PerColumn`].
+///
+/// [`Filling::Tabs`] is written in the `"tabs:SPACES:TAB_SIZE"` form that
+/// [`Filling::from_str`] accepts, followed by its tab stops if it has any,
+/// because its separator depends on where the columns start.
+impl fmt::Display for Filling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filling::Spaces(n) => write!(f, "{:n$}", ""),
+            Filling::Tabs {
+                spaces,
+                tab_size,
+                stops,
+            } => {
+                write!(f, "tabs:{spaces}:{tab_size}")?;
+                for (i, stop) in stops.iter().enumerate() {
+                    let prefix = if i == 0 { ':' } else { ',' };
+                    write!(f, "{prefix}{stop}")?;
+                }
+                Ok(())
+            }
+            _ => f.write_str(&self.separator(0)),
+        }
+    }
+}
+
+/// The error returned when a string starts like a [`Filling::Tabs`] but
+/// doesn't go on with two numbers.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseFillingError(String);
+
+impl fmt::Display for ParseFillingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid filling: {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseFillingError {}
+
+/// The width used by [`Width::Auto`] when no other width can be found, for
+/// example because the output is not a terminal.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// The width that a grid should fill.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Width {
+    /// A fixed number of terminal columns.
+    Columns(usize),
+
+    /// The width of the terminal, determined like GNU `ls` does.
+    ///
+    /// With the `terminal` feature enabled, the size of the terminal attached
+    /// to standard output is used. Otherwise, or if there is no terminal, the
+    /// `COLUMNS` environment variable is consulted. If that is not set to a
+    /// positive number either, or without the `std` feature, which is needed
+    /// to read it, [`DEFAULT_WIDTH`] is used.
+    Auto,
+
+    /// No limit on the width: all cells are put on a single row, like
+    /// `ls --width=0` does.
+    Unlimited,
+}
+
+impl Width {
+    /// The number of terminal columns this width stands for.
+    ///
+    /// For [`Width::Auto`], this looks up the width every time it is called.
+    /// [`Width::Unlimited`] resolves to `usize::MAX`.
+    pub fn resolve(self) -> usize {
+        match self {
+            Width::Columns(width) => width,
+            Width::Auto => auto_width(),
+            Width::Unlimited => usize::MAX,
+        }
+    }
+}
+
+impl From<usize> for Width {
+    fn from(width: usize) -> Self {
+        Width::Columns(width)
+    }
+}
+
+/// Determines the width for [`Width::Auto`].
+fn auto_width() -> usize {
+    #[cfg(feature = "terminal")]
+    if let Some(width) = terminal::terminal_width() {
+        return width;
+    }
+
+    #[cfg(feature = "std")]
+    if let Some(width) = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse().ok())
+        .filter(|&width| width > 0)
+    {
+        return width;
+    }
+
+    DEFAULT_WIDTH
+}
+
+/// The options for a grid view that should be passed to [`Grid::new`]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GridOptions {
+    /// The direction that the cells should be written in
+    pub direction: Direction,
+
+    /// The string to put in between each column of cells
+    pub filling: Filling,
+
+    /// The width to fill with the grid
+    pub width: Width,
+
+    /// What to do with cells that are wider than the grid
+    pub overflow: OverflowPolicy,
+
+    /// Another program whose layout to reproduce instead of searching for
+    /// the best one
+    pub compat: Compat,
+
+    /// Which of the layouts that fit the width to choose
+    pub strategy: Strategy,
+
+    /// Whether to spread the cells evenly over the columns when they go
+    /// down the columns, so that the last column isn't much shorter than
+    /// the others
+    ///
+    /// Each column then holds as many cells as the others or one fewer, as
+    /// long as that still fits the width.
+    pub balance_columns: bool,
+
+    /// Whether to avoid leaving a single cell on the last row when the cells
+    /// go along the rows
+    ///
+    /// A layout with one column fewer or one row more is used instead, if
+    /// it doesn't leave a single cell either and still fits the width.
+    pub avoid_orphans: bool,
+
+    /// Whether to choose the layout with fewer columns when the strategy
+    /// rates two layouts the same, instead of the one with more columns
+    pub prefer_fewer_columns: bool,
+
+    /// The percentile of the widths of the cells to size the columns by, or
+    /// `None` to size them by the widest cells
+    ///
+    /// The cells that are wider than this percentile of all the cells are
+    /// truncated, so that one very long cell doesn't take up the room of
+    /// several columns. With 95, the widest 5% of the cells are cut down.
+    pub width_percentile: Option<u8>,
+
+    /// The tab size to expand tabs within cells to, or `None` to leave tabs
+    /// as they are
+    ///
+    /// Tabs are replaced with spaces before the width of a cell is measured,
+    /// counting the columns from the start of the cell.
+    pub expand_tabs: Option<usize>,
+
+    /// Whether the suffixes given to [`Grid::new_with_suffixes`] are left out
+    /// when measuring the cells
+    ///
+    /// An excluded suffix is written in the padding after its cell, so the
+    /// columns line up by the cells alone, like some modes of `ls -F`.
+    pub exclude_suffixes: bool,
+
+    /// The number of spaces by which each level of depth given to
+    /// [`Grid::new_with_depths`] indents a cell
+    pub indent_width: usize,
+
+    /// The text to show instead of cells that are empty, or `None` to leave
+    /// them empty
+    ///
+    /// This keeps blank cells visible, so that the columns don't look
+    /// broken. The placeholder is measured like any other cell.
+    pub placeholder: Option<String>,
+
+    /// How to measure emoji sequences that are joined into a single symbol
+    pub emoji_width: EmojiWidth,
+
+    /// The string to end each line with
+    pub line_ending: LineEnding,
+
+    /// Whether to end the last line of the grid with the line ending as well
+    ///
+    /// Without it, the grid can be followed by more text on the same line,
+    /// such as a prompt.
+    pub final_line_ending: bool,
+
+    /// Whether to start each row with its number, counting from 1
+    ///
+    /// The numbers are right-aligned in a gutter that is as wide as the
+    /// largest number plus a space. The gutter is taken from the width that
+    /// is available to the cells.
+    pub line_numbers: bool,
+
+    /// The escape sequence to start every other row with, such as a
+    /// background colour, or `None` to not stripe the rows
+    ///
+    /// The style covers the whole width of the grid, including the padding
+    /// and separators, and is applied again after each reset within a cell.
+    /// The first row is not striped.
+    pub zebra: Option<String>,
+
+    /// The escape sequence to highlight selected cells with
+    ///
+    /// See [`Grid::select`]. The default is reverse video.
+    pub selection_style: String,
+
+    /// Whether to remove all escape sequences from the cells when the grid
+    /// is rendered, for plain output that can be piped to files or other
+    /// programs
+    ///
+    /// The cells are still measured by their visible width, so the layout is
+    /// the same as with the escape sequences in place. The zebra stripes and
+    /// the highlighting of selected cells are left out as well.
+    pub strip_ansi: bool,
+
+    /// Whether to place the cells in reverse order, starting from the last
+    /// one, in the given direction
+    pub reverse: bool,
+
+    /// A custom function to measure the width of cells with, or `None` to
+    /// use [`ansi_width`](ansi_width::ansi_width)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub width_fn: Option<WidthFn>,
+
+    /// A function to style the contents of each cell with while rendering,
+    /// or `None` to write the cells as they are
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub style_fn: Option<StyleFn>,
+}
+
+/// A function that measures how many columns a string takes up in the
+/// terminal.
+///
+/// This can be used to account for escape sequences or fonts that the
+/// default measurement gets wrong, such as icon fonts. The function is
+/// called with the contents of each cell (after tab expansion) when the grid
+/// is created.
+#[derive(Clone)]
+pub struct WidthFn(Arc<dyn Fn(&str) -> usize + Send + Sync>);
+
+impl WidthFn {
+    /// Wraps a function to measure cells with.
+    pub fn new(f: impl Fn(&str) -> usize + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Measures the given string.
+    pub fn measure(&self, s: &str) -> usize {
+        (self.0)(s)
+    }
+}
+
+impl fmt::Debug for WidthFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WidthFn(..)")
+    }
+}
+
+/// A function that styles the contents of a cell while the grid is being
+/// rendered, given the index of the cell and its contents.
+///
+/// The function is called after the cells have been measured and laid out,
+/// so the escape sequences it adds don't count towards the width of the
+/// cells. It is called with the text that is actually written, which is
+/// only part of the cell if the cell was truncated or wrapped, and must not
+/// change its width.
+#[derive(Clone)]
+pub struct StyleFn(Arc<StyleFnInner>);
+
+type StyleFnInner = dyn for<'a> Fn(usize, &'a str) -> Cow<'a, str> + Send + Sync;
+
+impl StyleFn {
+    /// Wraps a function to style cells with.
+    pub fn new(f: impl for<'a> Fn(usize, &'a str) -> Cow<'a, str> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Styles the contents of the cell with the given index.
+    pub fn style<'a>(&self, index: usize, contents: &'a str) -> Cow<'a, str> {
+        (self.0)(index, contents)
+    }
+}
+
+impl fmt::Debug for StyleFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StyleFn(..)")
+    }
+}
+
+/// How wide emoji sequences are taken to be.
+///
+/// Emoji such as 👩‍🔬 are made of several emoji joined by zero-width joiners
+/// or followed by skin tone modifiers. Terminals disagree about how wide
+/// these sequences are: some draw them as a single symbol, others draw each
+/// part separately.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum EmojiWidth {
+    /// The sum of the widths of the parts, so 👩‍🔬 is 4 columns wide.
+    #[default]
+    Sum,
+
+    /// The width of the first part, so the whole sequence is as wide as a
+    /// single emoji and 👩‍🔬 is 2 columns wide.
+    Cluster,
+}
+
+/// The string to end each line of the grid with.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum LineEnding {
+    /// A line feed (`"\n"`)
+    #[default]
+    Lf,
+
+    /// A carriage return followed by a line feed (`"\r\n"`), as used on
+    /// Windows
+    CrLf,
+
+    /// A NUL character (`"\0"`), like `ls --zero`, for output that is read
+    /// by other programs
+    Nul,
+
+    /// An arbitrary string
+    Custom(String),
+}
+
+impl LineEnding {
+    /// The string that ends each line.
+    pub fn as_str(&self) -> &str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Nul => "\0",
+            LineEnding::Custom(s) => s,
+        }
+    }
+}
+
+/// What to do when a cell is wider than the grid itself.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum OverflowPolicy {
+    /// Fall back to a single column, which is as wide as the widest cell and
+    /// therefore wider than the grid.
+    #[default]
+    Collapse,
+
+    /// Truncate the cells that are too wide to the width of the grid, marking
+    /// the cut with an ellipsis (`…`), and lay out the grid as usual.
+    Truncate,
+
+    /// Wrap the cells that are too wide onto continuation lines within their
+    /// column, and lay out the grid as usual. A row that contains a wrapped
+    /// cell takes up more than one line.
+    Wrap,
+}
+
+/// Another program whose choice of layout a grid should reproduce exactly.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Compat {
+    /// Search for the layout with the fewest lines, as this crate does.
+    #[default]
+    None,
+
+    /// Choose the number of columns like GNU `ls -C` and `ls -x` do.
+    ///
+    /// GNU `ls` measures every column but the last with the separator
+    /// included, only accepts a line that is strictly narrower than the
+    /// width, and picks the most columns rather than the fewest lines, so
+    /// its output sometimes has fewer columns than the default search
+    /// would find. [`Direction::TopToBottom`] matches `-C` and
+    /// [`Direction::LeftToRight`] matches `-x`.
+    ///
+    /// Like GNU `ls`, every row ends right after its last cell, so a short
+    /// row has no padding or separator at its end.
+    GnuLs,
+
+    /// Choose the number of columns like BSD and macOS `ls -C` and `ls -x`
+    /// do.
+    ///
+    /// BSD `ls` makes every column as wide as the widest cell plus the
+    /// separator, rounded up to the next tab stop, and fits as many of these
+    /// into the width as it can, counting the last column in full. Its
+    /// columns are one tab stop apart at the least, which
+    /// [`Filling::Spaces(1)`](Filling::Spaces) reproduces.
+    BsdLs,
+}
+
+/// Which of the layouts that fit the width a grid should choose.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Strategy {
+    /// The layout with the fewest rows, and of those the one with the most
+    /// columns.
+    #[default]
+    MinimizeRows,
+
+    /// The layout with the fewest rows, and of those the one that is the
+    /// narrowest.
+    MinimizeWidth,
+
+    /// The layout that is closest to a square, counting rows and columns,
+    /// even if it has more rows than it needs to.
+    ///
+    /// Of two layouts that are as close to a square, the one with more
+    /// columns is chosen, unless
+    /// [`GridOptions::prefer_fewer_columns`] is set.
+    Balanced,
+
+    /// The layout with as many columns as the widest cell fits into side by
+    /// side, which is found in a single pass over the cells instead of a
+    /// search.
+    ///
+    /// This is much faster for hundreds of thousands of cells, but may take
+    /// a row or so more than [`Strategy::MinimizeRows`] when the cells
+    /// differ in width.
+    Greedy,
+}
+
+impl GridOptions {
+    /// The layout of `ls`: cells go down the columns, separated by two
+    /// spaces, filling the width of the terminal.
+    ///
+    /// This is the same as [`GridOptions::default`].
+    pub fn ls() -> Self {
+        Self {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(2),
+            width: Width::Auto,
+            overflow: OverflowPolicy::Collapse,
+            compat: Compat::None,
+            strategy: Strategy::MinimizeRows,
+            balance_columns: false,
+            avoid_orphans: false,
+            prefer_fewer_columns: false,
+            width_percentile: None,
+            expand_tabs: None,
+            placeholder: None,
+            exclude_suffixes: false,
+            indent_width: 2,
+            emoji_width: EmojiWidth::Sum,
+            line_numbers: false,
+            zebra: None,
+            selection_style: "\x1b[7m".into(),
+            strip_ansi: false,
+            reverse: false,
+            line_ending: LineEnding::Lf,
+            final_line_ending: true,
+            width_fn: None,
+            style_fn: None,
+        }
+    }
+
+    /// Like [`GridOptions::ls`], but with a single space between the columns
+    /// to fit as much as possible on the screen.
+    pub fn dense() -> Self {
+        Self {
+            filling: Filling::Spaces(1),
+            ..Self::ls()
+        }
+    }
+
+    /// A table-like layout: cells go along the rows, with `" | "` between
+    /// the columns, filling the width of the terminal.
+    pub fn table() -> Self {
+        Self {
+            direction: Direction::LeftToRight,
+            filling: Filling::Text(" | ".into()),
+            ..Self::ls()
+        }
+    }
+}
+
+impl GridOptions {
+    /// Checks that the options can produce a sensible grid.
+    ///
+    /// [`Grid::new`] accepts any options, but degenerate ones silently lead
+    /// to a grid with a single column. Only a fixed [`Width::Columns`] can be
+    /// checked; the other widths are always accepted.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        if self.expand_tabs == Some(0) {
+            return Err(OptionsError::ZeroTabSize);
+        }
+        if let Some(percentile @ (0 | 101..)) = self.width_percentile {
+            return Err(OptionsError::PercentileOutOfRange(percentile));
+        }
+        if let Width::Columns(width) = self.width {
+            if width == 0 {
+                return Err(OptionsError::ZeroWidth);
+            }
+            let filling = self.filling.gap_width(0);
+            if filling > width {
+                return Err(OptionsError::FillingTooWide { filling, width });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GridOptions {
+    /// Measures how wide a cell will be when displayed with these options.
+    fn cell_width(&self, cell: &str) -> usize {
+        let text = self.display_text(cell);
+        match (&self.width_fn, self.emoji_width) {
+            (Some(width_fn), _) => width_fn.measure(&text),
+            (None, _) if is_printable_ascii(&text) => text.len(),
+            (None, EmojiWidth::Sum) => ansi_width(&text),
+            (None, EmojiWidth::Cluster) => cluster_width(&text),
+        }
+    }
+
+    /// How to measure the text of cells while truncating or wrapping them,
+    /// which agrees with [`GridOptions::cell_width`].
+    fn text_measure(&self) -> Measure<'_> {
+        match (&self.width_fn, self.emoji_width) {
+            (Some(width_fn), _) => Measure::Custom(width_fn),
+            (None, EmojiWidth::Sum) => Measure::Chars,
+            (None, EmojiWidth::Cluster) => Measure::Clusters,
+        }
+    }
+
+    /// Measures how wide a cell will be when displayed with these options.
+    ///
+    /// Only cells that are strings are measured; other cells are trusted to
+    /// know their own width.
+    fn measure(&self, cell: &impl Cell) -> usize {
+        match cell.as_str() {
+            Some(cell) => self.cell_width(cell),
+            None => cell.width(),
+        }
+    }
+
+    /// Measures every cell with [`GridOptions::measure`].
+    ///
+    /// With the `rayon` feature, the cells of huge grids are measured on
+    /// several threads.
+    fn measure_all<T: Cell>(&self, cells: &[T]) -> Vec<usize> {
+        #[cfg(feature = "rayon")]
+        if cells.len() > parallel::MAX_SERIAL_CELLS {
+            return self.measure_in_parallel(cells);
+        }
+        cells.iter().map(|cell| self.measure(cell)).collect()
+    }
+
+    /// The text of a cell as it should be displayed with these options,
+    /// before it is padded or truncated.
+    fn display_text<'a>(&'a self, cell: &'a str) -> Cow<'a, str> {
+        let cell = match &self.placeholder {
+            Some(placeholder) if cell.is_empty() => placeholder,
+            _ => cell,
+        };
+        match self.expand_tabs {
+            Some(tab_size) => expand_tabs(cell, tab_size),
+            None => Cow::Borrowed(cell),
+        }
+    }
+}
+
+impl Default for GridOptions {
+    fn default() -> Self {
+        Self::ls()
+    }
+}
+
+/// An error for options that can't produce a sensible grid.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum OptionsError {
+    /// The width is zero, so not even an empty cell would fit.
+    ZeroWidth,
+
+    /// The filling is wider than the grid, so there can never be more than
+    /// one column.
+    FillingTooWide {
+        /// The width of the filling.
+        filling: usize,
+        /// The width of the grid.
+        width: usize,
+    },
+
+    /// The tab size for expanding tabs is zero.
+    ZeroTabSize,
+
+    /// The percentile to size the columns by is not between 1 and 100.
+    PercentileOutOfRange(u8),
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::ZeroWidth => f.write_str("the width of the grid is zero"),
+            OptionsError::FillingTooWide { filling, width } => write!(
+                f,
+                "the filling is {filling} columns wide, but the grid is only {width} columns wide"
+            ),
+            OptionsError::ZeroTabSize => f.write_str("the tab size is zero"),
+            OptionsError::PercentileOutOfRange(percentile) => {
+                write!(f, "the percentile {percentile} is not between 1 and 100")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OptionsError {}
+
+/// A builder for a [`Grid`], as an alternative to [`Grid::new`].
+///
+/// Every option starts out with the value from [`GridOptions::default`] and
+/// can be changed with the chainable setters. The options are checked when
+/// the grid is built.
+///
+/// ```
+/// use term_grid::{Direction, GridBuilder};
+///
+/// let grid = GridBuilder::new()
+///     .direction(Direction::LeftToRight)
+///     .width(20)
+///     .cells(["one", "two", "three"])
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(grid.to_string(), "one  two  three\n");
+/// ```
+#[derive(Debug)]
+pub struct GridBuilder<T> {
+    options: GridOptions,
+    cells: Vec<T>,
+}
+
+impl<T> GridBuilder<T> {
+    /// Creates a builder with the default options and no cells.
+    pub fn new() -> Self {
+        Self {
+            options: GridOptions::default(),
+            cells: Vec::new(),
+        }
+    }
+
+    /// Replaces all the options at once.
+    pub fn options(mut self, options: GridOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the direction that the cells should be written in.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.options.direction = direction;
+        self
+    }
+
+    /// Sets the string to put in between each column of cells.
+    pub fn filling(mut self, filling: Filling) -> Self {
+        self.options.filling = filling;
+        self
+    }
+
+    /// Sets the width to fill with the grid.
+    pub fn width(mut self, width: impl Into<Width>) -> Self {
+        self.options.width = width.into();
+        self
+    }
+
+    /// Sets what to do with cells that are wider than the grid.
+    pub fn overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.options.overflow = overflow;
+        self
+    }
+
+    /// Sets the program whose layout to reproduce.
+    pub fn compat(mut self, compat: Compat) -> Self {
+        self.options.compat = compat;
+        self
+    }
+
+    /// Sets which of the layouts that fit the width to choose.
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.options.strategy = strategy;
+        self
+    }
+
+    /// Sets whether to spread the cells evenly over the columns.
+    pub fn balance_columns(mut self, balance_columns: bool) -> Self {
+        self.options.balance_columns = balance_columns;
+        self
+    }
+
+    /// Sets whether to avoid leaving a single cell on the last row.
+    pub fn avoid_orphans(mut self, avoid_orphans: bool) -> Self {
+        self.options.avoid_orphans = avoid_orphans;
+        self
+    }
+
+    /// Sets whether to choose the layout with fewer columns on a tie.
+    pub fn prefer_fewer_columns(mut self, prefer_fewer_columns: bool) -> Self {
+        self.options.prefer_fewer_columns = prefer_fewer_columns;
+        self
+    }
+
+    /// Sets the percentile of the widths of the cells to size the columns
+    /// by.
+    pub fn width_percentile(mut self, width_percentile: Option<u8>) -> Self {
+        self.options.width_percentile = width_percentile;
+        self
+    }
+
+    /// Sets the tab size to expand tabs within cells to.
+    pub fn expand_tabs(mut self, tab_size: usize) -> Self {
+        self.options.expand_tabs = Some(tab_size);
+        self
+    }
+
+    /// Sets whether suffixes are left out when measuring the cells.
+    pub fn exclude_suffixes(mut self, exclude_suffixes: bool) -> Self {
+        self.options.exclude_suffixes = exclude_suffixes;
+        self
+    }
+
+    /// Sets the number of spaces by which each level of depth indents a
+    /// cell.
+    pub fn indent_width(mut self, indent_width: usize) -> Self {
+        self.options.indent_width = indent_width;
+        self
+    }
+
+    /// Sets the text to show instead of cells that are empty.
+    pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.options.placeholder = Some(placeholder.into());
+        self
+    }
+
+    /// Sets how to measure emoji sequences.
+    pub fn emoji_width(mut self, emoji_width: EmojiWidth) -> Self {
+        self.options.emoji_width = emoji_width;
+        self
+    }
+
+    /// Sets whether to start each row with its number.
+    pub fn line_numbers(mut self, line_numbers: bool) -> Self {
+        self.options.line_numbers = line_numbers;
+        self
+    }
+
+    /// Sets the escape sequence to start every other row with.
+    pub fn zebra(mut self, style: impl Into<String>) -> Self {
+        self.options.zebra = Some(style.into());
+        self
+    }
+
+    /// Sets the escape sequence to highlight selected cells with.
+    pub fn selection_style(mut self, style: impl Into<String>) -> Self {
+        self.options.selection_style = style.into();
+        self
+    }
+
+    /// Sets whether to remove all escape sequences from the cells when
+    /// rendering.
+    pub fn strip_ansi(mut self, strip_ansi: bool) -> Self {
+        self.options.strip_ansi = strip_ansi;
+        self
+    }
+
+    /// Sets whether to place the cells in reverse order.
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.options.reverse = reverse;
+        self
+    }
+
+    /// Sets the string to end each line with.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.options.line_ending = line_ending;
+        self
+    }
+
+    /// Sets whether to end the last line of the grid with the line ending.
+    pub fn final_line_ending(mut self, final_line_ending: bool) -> Self {
+        self.options.final_line_ending = final_line_ending;
+        self
+    }
+
+    /// Sets a custom function to measure the width of cells with.
+    pub fn width_fn(mut self, f: impl Fn(&str) -> usize + Send + Sync + 'static) -> Self {
+        self.options.width_fn = Some(WidthFn::new(f));
+        self
+    }
+
+    /// Sets a function to style the contents of each cell with while
+    /// rendering.
+    pub fn style_fn(
+        mut self,
+        f: impl for<'a> Fn(usize, &'a str) -> Cow<'a, str> + Send + Sync + 'static,
+    ) -> Self {
+        self.options.style_fn = Some(StyleFn::new(f));
+        self
+    }
+
+    /// Adds cells to the grid, after the ones that were already added.
+    pub fn cells(mut self, cells: impl IntoIterator<Item = T>) -> Self {
+        self.cells.extend(cells);
+        self
+    }
+}
+
+impl<T: Cell> GridBuilder<T> {
+    /// Checks the options and lays out the grid.
+    pub fn build(self) -> Result<Grid<T>, OptionsError> {
+        Grid::try_new(self.cells, self.options)
+    }
+}
+
+impl<T> FromIterator<T> for GridBuilder<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(cells: I) -> Self {
+        Self::new().cells(cells)
+    }
+}
+
+impl<T> Extend<T> for GridBuilder<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, cells: I) {
+        self.cells.extend(cells);
+    }
+}
+
+impl<T> Default for GridBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The arrangement of the cells of a grid: how many rows and columns there
+/// are, how wide each column is and which cell goes where.
+///
+/// A layout is computed when a [`Grid`] is created and can be retrieved with
+/// [`Grid::layout`]. It can be inspected on its own, or kept around and used
+/// to render the same cells again later with [`Grid::with_layout`], skipping
+/// the search for the best layout.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Layout {
+    /// The direction in which the cells are placed.
+    direction: Direction,
+
+    /// Whether the cells are placed starting from the last one.
+    reversed: bool,
+
+    /// The number of cells in the grid.
+    num_cells: usize,
+
+    /// The number of lines in the grid.
+    num_lines: usize,
+
+    /// The width of each column in the grid. The length of this vector serves
+    /// as the number of columns.
+    widths: ColumnWidths,
+
+    /// Where each cell goes, for layouts whose cells don't simply flow in
+    /// their direction, such as ones split into groups.
+    placement: Option<Placement>,
+}
+
+/// The positions of the cells of a [`Layout`] that is split into groups,
+/// which can't be computed from the shape of the layout alone.
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct Placement {
+    /// The row and column of each cell.
+    positions: Vec<(usize, usize)>,
+
+    /// The index of the cell at each position, row by row.
+    table: Vec<Option<usize>>,
+
+    /// The rows that hold a group header, with the index of that header.
+    headers: Vec<(usize, usize)>,
+}
+
+impl Layout {
+    /// The number of rows in the layout.
+    pub fn row_count(&self) -> usize {
+        self.num_lines
+    }
+
+    /// The number of columns in the layout.
+    pub fn column_count(&self) -> usize {
+        self.widths.len()
+    }
+
+    /// The width of each column, not including separators.
+    pub fn column_widths(&self) -> &[usize] {
+        &self.widths
+    }
+
+    /// The number of cells placed by this layout.
+    pub fn cell_count(&self) -> usize {
+        self.num_cells
+    }
+
+    /// The direction in which the cells are placed.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Whether the cells are placed in reverse order, starting from the last
+    /// one.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// The row and column of the cell with the given index, or `None` if the
+    /// index is out of bounds.
+    pub fn position(&self, index: usize) -> Option<(usize, usize)> {
+        (index < self.num_cells).then(|| self.place(index))
+    }
+
+    /// The index of the cell at the given row and column, or `None` if that
+    /// position is outside the layout or empty.
+    pub fn index_at(&self, row: usize, column: usize) -> Option<usize> {
+        if row >= self.num_lines || column >= self.widths.len() {
+            return None;
+        }
+        if let Some(placement) = &self.placement {
+            return placement.table[row * self.widths.len() + column];
+        }
+
+        let index = match self.direction {
+            Direction::LeftToRight => row * self.widths.len() + column,
+            Direction::TopToBottom => row + self.num_lines * column,
+            Direction::RightToLeft => row * self.widths.len() + (self.widths.len() - 1 - column),
+            Direction::BottomToTop => (self.num_lines - 1 - row) + self.num_lines * column,
+            Direction::Snake if row % 2 == 0 => row * self.widths.len() + column,
+            Direction::Snake => row * self.widths.len() + (self.widths.len() - 1 - column),
+        };
+        if index >= self.num_cells {
+            None
+        } else if self.reversed {
+            Some(self.num_cells - 1 - index)
+        } else {
+            Some(index)
+        }
+    }
+
+    /// Returns an iterator over the rows of the layout, each of which is an
+    /// iterator over the indices of the cells in that row, from left to right.
+    pub fn rows(&self) -> Rows<'_> {
+        Rows {
+            layout: self,
+            next: 0,
+        }
+    }
+
+    /// Returns an iterator over the columns of the layout, each of which is
+    /// an iterator over the indices of the cells in that column, from top to
+    /// bottom.
+    pub fn columns(&self) -> Columns<'_> {
+        Columns {
+            layout: self,
+            next: 0,
+        }
+    }
+
+    /// The row and column of the cell with the given index, which must be in
+    /// bounds.
+    fn place(&self, index: usize) -> (usize, usize) {
+        if let Some(placement) = &self.placement {
+            return placement.positions[index];
+        }
+        let index = if self.reversed {
+            self.num_cells - 1 - index
+        } else {
+            index
+        };
+        match self.direction {
+            Direction::LeftToRight => {
+                let columns = self.widths.len();
+                (index / columns, index % columns)
+            }
+            Direction::TopToBottom => (index % self.num_lines, index / self.num_lines),
+            Direction::RightToLeft => {
+                let columns = self.widths.len();
+                (index / columns, columns - 1 - index % columns)
+            }
+            Direction::BottomToTop => (
+                self.num_lines - 1 - index % self.num_lines,
+                index / self.num_lines,
+            ),
+            Direction::Snake => {
+                let columns = self.widths.len();
+                let row = index / columns;
+                if row % 2 == 0 {
+                    (row, index % columns)
+                } else {
+                    (row, columns - 1 - index % columns)
+                }
+            }
+        }
+    }
+
+    /// The last column taken up by the cell at the given row and column,
+    /// which is further to the right than the column itself if the cell
+    /// spans several columns.
+    fn span_end(&self, row: usize, column: usize) -> usize {
+        let Some(placement) = &self.placement else {
+            return column;
+        };
+        let columns = self.widths.len();
+        let cells = &placement.table[row * columns..(row + 1) * columns];
+        let mut end = column;
+        while end + 1 < columns && cells[end + 1] == cells[column] {
+            end += 1;
+        }
+        end
+    }
+
+    /// The index of the group header in the given row, if there is one.
+    fn header_at(&self, row: usize) -> Option<usize> {
+        let headers = &self.placement.as_ref()?.headers;
+        let i = headers.binary_search_by_key(&row, |&(row, _)| row).ok()?;
+        Some(headers[i].1)
+    }
+
+    fn total_width(&self, filling: &Filling) -> usize {
+        let values = self.widths.iter().sum::<usize>();
+        values + filling.separators_width(self.widths.len())
+    }
+}
+
+/// An iterator over the rows of a [`Layout`].
+///
+/// This is created by [`Layout::rows`] and [`Grid::rows`].
+#[derive(Debug, Clone)]
+pub struct Rows<'a> {
+    layout: &'a Layout,
+    next: usize,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = CellIndices<'a>;
+
+    fn next(&mut self) -> Option<CellIndices<'a>> {
+        if self.next >= self.layout.num_lines {
+            return None;
+        }
+        let row = self.next;
+        self.next += 1;
+        Some(CellIndices {
+            layout: self.layout,
+            fixed: row,
+            along_row: true,
+            next: 0,
+        })
+    }
+}
+
+/// An iterator over the columns of a [`Layout`].
+///
+/// This is created by [`Layout::columns`] and [`Grid::columns`].
+#[derive(Debug, Clone)]
+pub struct Columns<'a> {
+    layout: &'a Layout,
+    next: usize,
+}
+
+impl<'a> Iterator for Columns<'a> {
+    type Item = CellIndices<'a>;
+
+    fn next(&mut self) -> Option<CellIndices<'a>> {
+        if self.next >= self.layout.widths.len() {
+            return None;
+        }
+        let column = self.next;
+        self.next += 1;
+        Some(CellIndices {
+            layout: self.layout,
+            fixed: column,
+            along_row: false,
+            next: 0,
+        })
+    }
+}
+
+/// An iterator over the indices of the cells in a single row or column of a
+/// [`Layout`], skipping empty positions.
+#[derive(Debug, Clone)]
+pub struct CellIndices<'a> {
+    layout: &'a Layout,
+    fixed: usize,
+    along_row: bool,
+    next: usize,
+}
+
+impl Iterator for CellIndices<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let len = if self.along_row {
+            self.layout.widths.len()
+        } else {
+            self.layout.num_lines
+        };
+
+        while self.next < len {
+            let position = self.next;
+            self.next += 1;
+            let index = if self.along_row {
+                self.layout.index_at(self.fixed, position)
+            } else {
+                self.layout.index_at(position, self.fixed)
+            };
+            if index.is_some() {
+                // A cell that spans several columns is only listed once.
+                if self.along_row {
+                    self.next = self.layout.span_end(self.fixed, position) + 1;
+                }
+                return index;
+            }
+        }
+
+        None
+    }
+}
+
+/// How far a grid exceeds the width it was given, as reported by
+/// [`Grid::overflow`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Overflow {
+    /// The number of columns by which the grid is wider than allowed.
+    pub exceeded_by: usize,
+
+    /// The indices of the cells that are wider than the allowed width on
+    /// their own.
+    pub offending_cells: Vec<usize>,
+}
+
+/// Something that can be put in a grid.
+///
+/// This is implemented for everything that implements `AsRef<str>`, such as
+/// `String` and `&str`. Implement it for other types to put them in a grid
+/// without converting them to strings first, for example to use cells that
+/// are styled and measured already.
+///
+/// Cells that aren't strings are written to a string when the grid is
+/// rendered, which is then truncated or wrapped like any other cell. The
+/// options that change how cells are measured, such as
+/// [`GridOptions::expand_tabs`] and [`GridOptions::width_fn`], only apply to
+/// cells that are strings.
+pub trait Cell {
+    /// The number of columns the cell takes up in the terminal.
+    fn width(&self) -> usize;
+
+    /// Writes the contents of the cell, including any escape sequences.
+    fn write(&self, f: &mut dyn fmt::Write) -> fmt::Result;
+
+    /// The number of lines the cell takes up.
+    ///
+    /// A cell that is taller than one line writes its lines separated by
+    /// `\n`, and they are placed below each other, so that the row the cell
+    /// is in gets as tall as its tallest cell. The default implementation
+    /// returns 1.
+    fn height(&self) -> usize {
+        1
+    }
+
+    /// The contents of the cell, if it is a string.
+    ///
+    /// This is used to avoid writing the cell to a new string when
+    /// rendering it. The default implementation returns `None`.
+    fn as_str(&self) -> Option<&str> {
+        None
+    }
+
+    /// The contents of the cell, written to a string if it isn't one.
+    fn text(&self) -> Cow<'_, str> {
+        match self.as_str() {
+            Some(s) => Cow::Borrowed(s),
+            None => {
+                let mut text = String::new();
+                self.write(&mut text)
+                    .expect("writing to a String cannot fail");
+                Cow::Owned(text)
+            }
+        }
+    }
+}
+
+impl<S: AsRef<str>> Cell for S {
+    fn width(&self) -> usize {
+        ansi_width(self.as_ref())
+    }
+
+    fn write(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        f.write_str(self.as_ref())
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Some(self.as_ref())
+    }
+}
+
+/// A cell that formats a [`Display`](fmt::Display) value when the grid is
+/// rendered, instead of being turned into a `String` up front.
+///
+/// The width is measured by formatting the value once when the grid is
+/// created, unless it is given with [`DisplayCell::with_width`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayCell<D> {
+    value: D,
+    width: Option<usize>,
+}
+
+impl<D: fmt::Display> DisplayCell<D> {
+    /// Wraps a value whose width is measured by formatting it.
+    pub fn new(value: D) -> Self {
+        Self { value, width: None }
+    }
+
+    /// Wraps a value whose width is already known.
+    pub fn with_width(value: D, width: usize) -> Self {
+        Self {
+            value,
+            width: Some(width),
+        }
+    }
+
+    /// The wrapped value.
+    pub fn value(&self) -> &D {
+        &self.value
+    }
+}
+
+impl<D: fmt::Display> From<D> for DisplayCell<D> {
+    fn from(value: D) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<D: fmt::Display> Cell for DisplayCell<D> {
+    fn width(&self) -> usize {
+        match self.width {
+            Some(width) => width,
+            None => ansi_width(&self.value.to_string()),
+        }
+    }
+
+    fn write(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+/// Everything needed to format the cells with the grid options.
+#[derive(Debug)]
+pub struct Grid<T: Cell, M = ()> {
+    options: GridOptions,
+    cells: Vec<T>,
+    /// The metadata of each cell, or nothing if the grid has none.
+    meta: Vec<M>,
+    widths: CellWidths,
+    widest_cell_width: usize,
+    /// The width of the cells at [`GridOptions::width_percentile`], if it is
+    /// narrower than the widest cell.
+    percentile_width: Option<usize>,
+    /// The width that the layout was computed for.
+    available_width: usize,
+    layout: Layout,
+    selected: BTreeSet<usize>,
+    suffixes: Vec<String>,
+    /// The index each cell had before the grid was sorted, or empty if it
+    /// hasn't been sorted.
+    order: Vec<usize>,
+    /// The index of the first cell of each group, with the group's header.
+    headers: Vec<(usize, String)>,
+    /// The indices of the cells that start a new row or column.
+    breaks: BTreeSet<usize>,
+    /// The ranges of cells that have to end up in the same row or column.
+    kept_together: Vec<Range<usize>>,
+    /// The number of columns taken up by the cells that span more than one.
+    spans: BTreeMap<usize, usize>,
+    /// For a grid that shows two lists side by side, the number of cells in
+    /// the one on the left.
+    pair_split: Option<usize>,
+    /// The depth of indentation of each cell, or empty if none is indented.
+    depths: Vec<usize>,
+    /// What the rows are padded and separated with, worked out once for each
+    /// layout.
+    plan: RenderPlan,
+}
+
+impl<T: Cell> Grid<T> {
+    /// Creates a new grid view with the given cells and options
+    ///
+    /// With the `rayon` feature, the cells of grids with more than a few
+    /// thousand cells are measured on several threads.
+    pub fn new(cells: Vec<T>, options: GridOptions) -> Self {
+        let widths = options.measure_all(&cells);
+        Self::new_with_widths(cells, widths, options)
+    }
+
+    /// Creates a new grid view with the given cells and options, using the
+    /// given display widths instead of measuring the cells.
+    ///
+    /// This is useful when the caller already knows how wide each cell is,
+    /// for example because it built the styled strings itself. The widths are
+    /// trusted as-is, so neither [`GridOptions::width_fn`] nor
+    /// [`GridOptions::expand_tabs`] is used to measure the cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of widths differs from the number of cells.
+    pub fn new_with_widths(cells: Vec<T>, widths: Vec<usize>, options: GridOptions) -> Self {
+        assert_eq!(
+            cells.len(),
+            widths.len(),
+            "every cell needs exactly one width"
+        );
+        Self::from_widths(cells, Vec::new(), widths, options)
+    }
+
+    /// Creates a new grid view with the given cells and options, writing a
+    /// suffix after each cell, such as the `/`, `*` or `@` that `ls -F` uses
+    /// to classify files.
+    ///
+    /// The suffixes are measured with [`ansi_width`](ansi_width::ansi_width)
+    /// and count towards the width of the cells, unless
+    /// [`GridOptions::exclude_suffixes`] is set. They are never truncated or
+    /// wrapped and aren't passed to [`GridOptions::style_fn`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of suffixes differs from the number of cells.
+    pub fn new_with_suffixes(cells: Vec<T>, suffixes: Vec<String>, options: GridOptions) -> Self {
+        assert_eq!(
+            cells.len(),
+            suffixes.len(),
+            "every cell needs exactly one suffix"
+        );
+        let widths = (cells.iter().zip(&suffixes))
+            .map(|(cell, suffix)| {
+                let width = options.measure(cell);
+                if options.exclude_suffixes {
+                    width
+                } else {
+                    width + ansi_width(suffix)
+                }
+            })
+            .collect();
+        let mut grid = Self::new_with_widths(cells, widths, options);
+        grid.suffixes = suffixes;
+        grid
+    }
+
+    /// Creates a new grid view with the given cells and options, indenting
+    /// each cell by its depth, for hierarchical listings such as trees.
+    ///
+    /// Each level of depth indents a cell by
+    /// [`GridOptions::indent_width`] spaces, which count towards the width
+    /// of the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of depths differs from the number of cells.
+    pub fn new_with_depths(cells: Vec<T>, depths: Vec<usize>, options: GridOptions) -> Self {
+        assert_eq!(
+            cells.len(),
+            depths.len(),
+            "every cell needs exactly one depth"
+        );
+        let widths = (cells.iter().zip(&depths))
+            .map(|(cell, depth)| options.measure(cell) + depth * options.indent_width)
+            .collect();
+        let mut grid = Self::new_with_widths(cells, widths, options);
+        grid.depths = depths;
+        grid
+    }
+
+    /// Creates a new grid view with the cells split into groups, each of
+    /// which starts with a header line such as `Documents:`.
+    ///
+    /// The groups are laid out one below the other with the same columns, so
+    /// that they line up. Each group starts on a new row, and its header is
+    /// written on a row of its own, spanning the whole grid. The headers are
+    /// written as they are and aren't measured, so a header wider than the
+    /// grid sticks out past it.
+    pub fn new_grouped(groups: Vec<(String, Vec<T>)>, options: GridOptions) -> Self {
+        let mut cells = Vec::new();
+        let mut headers = Vec::with_capacity(groups.len());
+        for (header, group) in groups {
+            headers.push((cells.len(), header));
+            cells.extend(group);
+        }
+
+        let widths = options.measure_all(&cells);
+        let mut grid = Self::new_with_widths(cells, widths, options);
+        grid.headers = headers;
+        grid.relayout();
+        grid
+    }
+
+    /// Creates a new grid view that shows two lists of cells side by side,
+    /// like a diff: the old cells on the left and the new cells on the right,
+    /// paired up row by row and separated by `marker`.
+    ///
+    /// The marker takes the place of the filling in the options. When the
+    /// two columns don't fit the width together, the wider one is narrowed
+    /// until they do, and the cells that no longer fit are truncated.
+    /// Cells added later with [`Grid::push`] go on the right.
+    pub fn side_by_side(old: Vec<T>, new: Vec<T>, marker: &str, options: GridOptions) -> Self {
+        let options = GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Text(marker.to_string()),
+            ..options
+        };
+        let split = old.len();
+        let mut cells = old;
+        cells.extend(new);
+
+        let widths = options.measure_all(&cells);
+        let mut grid = Self::new_with_widths(cells, widths, options);
+        grid.pair_split = Some(split);
+        grid.relayout();
+        grid
+    }
+
+    /// Creates a new grid view like [`Grid::new`], but first checks the
+    /// options with [`GridOptions::validate`].
+    pub fn try_new(cells: Vec<T>, options: GridOptions) -> Result<Self, OptionsError> {
+        options.validate()?;
+        Ok(Self::new(cells, options))
+    }
+
+    /// Creates a grid view that uses a previously computed layout instead of
+    /// searching for one.
+    ///
+    /// Returns `None` if the layout does not fit the cells: when it places a
+    /// different number of cells, or when a cell is wider than the column it
+    /// is placed in. The direction and order of the layout take precedence
+    /// over the ones in the options.
+    pub fn with_layout(cells: Vec<T>, options: GridOptions, layout: Layout) -> Option<Self> {
+        if layout.num_cells != cells.len() {
+            return None;
+        }
+
+        let widths: Vec<usize> = options.measure_all(&cells);
+        for (index, &width) in widths.iter().enumerate() {
+            let (_, column) = layout.position(index)?;
+            if width > layout.widths[column] {
+                return None;
+            }
+        }
+        let widest_cell_width = widths.iter().copied().max().unwrap_or(0);
+
+        let mut grid = Self {
+            available_width: options.width.resolve(),
+            options,
+            cells,
+            widths: CellWidths::new(widths),
+            widest_cell_width,
+            percentile_width: None,
+            layout,
+            meta: Vec::new(),
+            selected: BTreeSet::new(),
+            suffixes: Vec::new(),
+            order: Vec::new(),
+            headers: Vec::new(),
+            breaks: BTreeSet::new(),
+            kept_together: Vec::new(),
+            spans: BTreeMap::new(),
+            pair_split: None,
+            depths: Vec::new(),
+            plan: RenderPlan::default(),
+        };
+        grid.plan = RenderPlan::new(&grid);
+        Some(grid)
+    }
+
+    /// Adds a cell to the end of the grid and lays it out again.
+    ///
+    /// Only the new cell is measured; the widths of the other cells are
+    /// reused. Use [`Grid::extend`] to add several cells with a single
+    /// layout search.
+    pub fn push(&mut self, cell: T) {
+        self.extend(core::iter::once(cell));
+    }
+
+    /// Adds cells to the end of the grid and lays it out again once they
+    /// have all been added.
+    ///
+    /// Only the new cells are measured; the widths of the other cells are
+    /// reused.
+    pub fn extend(&mut self, cells: impl IntoIterator<Item = T>) {
+        let mut next_original = self.order.iter().max().map_or(0, |&i| i + 1);
+        for cell in cells {
+            if !self.order.is_empty() {
+                self.order.push(next_original);
+                next_original += 1;
+            }
+            if !self.meta.is_empty() {
+                self.meta.push(());
+            }
+            if !self.suffixes.is_empty() {
+                self.suffixes.push(String::new());
+            }
+            if !self.depths.is_empty() {
+                self.depths.push(0);
+            }
+            let width = self.options.measure(&cell);
+            self.widest_cell_width = self.widest_cell_width.max(width);
+            self.widths.push(width);
+            self.cells.push(cell);
+        }
+        self.relayout();
+    }
+}
+
+#[cfg(feature = "std")]
+impl Grid<String> {
+    /// Creates a new grid view from OS strings, such as the file names
+    /// returned by [`std::fs::read_dir`].
+    ///
+    /// Names that aren't valid UTF-8 are converted lossily, replacing invalid
+    /// sequences with `U+FFFD REPLACEMENT CHARACTER`, and are measured in
+    /// their converted form.
+    pub fn from_os_strings<I>(names: I, options: GridOptions) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        let cells = names
+            .into_iter()
+            .map(|name| name.as_ref().to_string_lossy().into_owned())
+            .collect();
+        Self::new(cells, options)
+    }
+
+    /// Creates a new grid view from paths, converting them lossily like
+    /// [`Grid::from_os_strings`].
+    pub fn from_paths<I>(paths: I, options: GridOptions) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        let cells = paths
+            .into_iter()
+            .map(|path| path.as_ref().to_string_lossy().into_owned())
+            .collect();
+        Self::new(cells, options)
+    }
+}
+
+impl<'a, T> Grid<&'a T>
+where
+    &'a T: Cell,
+{
+    /// Creates a new grid view that borrows its cells from a slice, such as
+    /// a `&[String]` or `&[&str]`, instead of taking ownership of them.
+    pub fn from_slice(cells: &'a [T], options: GridOptions) -> Self {
+        Self::new(cells.iter().collect(), options)
+    }
+}
+
+impl<T: Cell, M> Grid<T, M> {
+    /// Creates a new grid view with the given cells and options, keeping
+    /// the metadata that goes with each cell.
+    ///
+    /// The metadata can be anything, such as the file that a cell describes,
+    /// and can be looked up with [`Grid::meta`] and [`Grid::meta_at`], so
+    /// that it doesn't have to be kept in a separate map from cell indices.
+    pub fn new_with_meta(cells: Vec<(T, M)>, options: GridOptions) -> Self {
+        let (cells, meta): (Vec<T>, Vec<M>) = cells.into_iter().unzip();
+        let widths = options.measure_all(&cells);
+        Self::from_widths(cells, meta, widths, options)
+    }
+
+    /// Creates a grid view from cells whose widths are already known, and
+    /// lays it out.
+    fn from_widths(cells: Vec<T>, meta: Vec<M>, widths: Vec<usize>, options: GridOptions) -> Self {
+        let widest_cell_width = widths.iter().copied().max().unwrap_or(0);
+
+        let mut grid = Self {
+            layout: Layout {
+                direction: options.direction,
+                reversed: options.reverse,
+                num_cells: cells.len(),
+                num_lines: 0,
+                widths: ColumnWidths::default(),
+                placement: None,
+            },
+            options,
+            cells,
+            meta,
+            widths: CellWidths::new(widths),
+            widest_cell_width,
+            percentile_width: None,
+            available_width: 0,
+            selected: BTreeSet::new(),
+            suffixes: Vec::new(),
+            order: Vec::new(),
+            headers: Vec::new(),
+            breaks: BTreeSet::new(),
+            kept_together: Vec::new(),
+            spans: BTreeMap::new(),
+            pair_split: None,
+            depths: Vec::new(),
+            plan: RenderPlan::default(),
+        };
+
+        grid.relayout();
+        grid
+    }
+
+    /// Lays the grid out again for a different width.
+    ///
+    /// The widths of the cells were measured when the grid was created, so
+    /// only the layout is recomputed. This makes it much cheaper than
+    /// creating a new grid, for example when the terminal is resized.
+    /// Refitting to [`Width::Auto`] looks up the width of the terminal again.
+    pub fn refit(&mut self, width: impl Into<Width>) {
+        self.options.width = width.into();
+        self.relayout();
+    }
+
+    /// Swaps the rows and columns of the grid, so that the cell in row `r`
+    /// and column `c` moves to row `c` and column `r`.
+    ///
+    /// The cells keep their place relative to each other, but are now read
+    /// in the transposed direction: [`Direction::LeftToRight`] becomes
+    /// [`Direction::TopToBottom`] and vice versa, and
+    /// [`Direction::RightToLeft`] becomes [`Direction::BottomToTop`] and vice
+    /// versa. The widths of the columns are recomputed, but no new layout is
+    /// searched for, so the transposed grid might not fit the available
+    /// width; see [`Grid::overflow`].
+    ///
+    /// Returns `false` and leaves the grid unchanged for
+    /// [`Direction::Snake`], which has no transposed counterpart, and for
+    /// grids split into groups or sections.
+    pub fn transpose(&mut self) -> bool {
+        if self.layout.placement.is_some() {
+            return false;
+        }
+        let direction = match self.layout.direction {
+            Direction::LeftToRight => Direction::TopToBottom,
+            Direction::TopToBottom => Direction::LeftToRight,
+            Direction::RightToLeft => Direction::BottomToTop,
+            Direction::BottomToTop => Direction::RightToLeft,
+            Direction::Snake => return false,
+        };
+        self.options.direction = direction;
+        self.options.reverse = self.layout.reversed;
+
+        let num_lines = self.layout.widths.len();
+        let num_columns = self.layout.num_lines;
+        self.layout = self.compute_dimensions(&self.layout_widths(), num_lines, num_columns);
+        self.plan = RenderPlan::new(self);
+        true
+    }
+
+    /// Replaces the options of the grid, measuring the cells and laying the
+    /// grid out again.
+    ///
+    /// This is useful together with the [`FromIterator`] implementation,
+    /// which uses the default options. The suffixes, depths, metadata and
+    /// selection of the cells are kept.
+    pub fn with_options(mut self, options: GridOptions) -> Self {
+        self.options = options;
+        let widths = (0..self.cells.len())
+            .map(|i| {
+                self.options.measure(&self.cells[i]) + self.counted_suffix_width(i) + self.indent(i)
+            })
+            .collect();
+        self.widths = CellWidths::new(widths);
+        self.widest_cell_width = self.widths.max();
+        self.relayout();
+        self
+    }
+
+    /// Replaces the cell with the given index and lays the grid out again,
+    /// returning the old cell.
+    ///
+    /// The suffix, depth, metadata and selection of the cell are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is past the last cell.
+    pub fn set(&mut self, index: usize, cell: T) -> T {
+        let width =
+            self.options.measure(&cell) + self.counted_suffix_width(index) + self.indent(index);
+        let old = core::mem::replace(&mut self.cells[index], cell);
+        self.widths.set(index, width);
+        self.widest_cell_width = self.widths.max();
+        self.relayout();
+        old
+    }
+
+    /// Removes the cell with the given index and lays the grid out again,
+    /// returning the removed cell.
+    ///
+    /// The suffix, depth and metadata of the cell are removed with it, and
+    /// the cells after it move up one index, along with their selection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is past the last cell.
+    pub fn remove(&mut self, index: usize) -> T {
+        let cell = self.cells.remove(index);
+        self.widths.remove(index);
+        if index < self.meta.len() {
+            self.meta.remove(index);
+        }
+        if index < self.suffixes.len() {
+            self.suffixes.remove(index);
+        }
+        if index < self.depths.len() {
+            self.depths.remove(index);
+        }
+        if index < self.order.len() {
+            self.order.remove(index);
+        }
+        for (first, _) in &mut self.headers {
+            if *first > index {
+                *first -= 1;
+            }
+        }
+        self.spans = (self.spans.iter())
+            .filter(|(&i, _)| i != index)
+            .map(|(&i, &span)| (if i > index { i - 1 } else { i }, span))
+            .collect();
+        if let Some(split) = &mut self.pair_split {
+            *split -= usize::from(index < *split);
+        }
+        for range in &mut self.kept_together {
+            range.start -= usize::from(range.start > index);
+            range.end -= usize::from(range.end > index);
+        }
+        // The cell after the removed one takes over its break.
+        self.breaks = (self.breaks.iter())
+            .map(|&i| if i > index { i - 1 } else { i })
+            .filter(|&i| i < self.cells.len())
+            .collect();
+        self.selected = (self.selected.iter())
+            .filter(|&&i| i != index)
+            .map(|&i| if i > index { i - 1 } else { i })
+            .collect();
+        self.widest_cell_width = self.widths.max();
+        self.relayout();
+        cell
+    }
+
+    /// Marks the cells with the given indices as starting a new section and
+    /// lays the grid out again, replacing any earlier breaks.
+    ///
+    /// When the cells are placed in rows, as with
+    /// [`Direction::LeftToRight`], each section starts on a new row. When
+    /// they are placed in columns, as with [`Direction::TopToBottom`], each
+    /// section starts in a new column, so items that belong together aren't
+    /// split across a boundary. Indices past the last cell are ignored.
+    pub fn set_breaks(&mut self, indices: impl IntoIterator<Item = usize>) {
+        let len = self.cells.len();
+        self.breaks = indices.into_iter().filter(|&i| i < len).collect();
+        self.relayout();
+    }
+
+    /// Makes the cell with the given index span `columns` columns and lays
+    /// the grid out again.
+    ///
+    /// The cell takes up the widths of the columns it spans and the
+    /// separators between them, and doesn't count towards their widths
+    /// unless it is too wide for all of them together. A span larger than
+    /// the number of columns covers the whole row, which is useful for
+    /// banners or summaries. When the cells are placed in rows, a cell that
+    /// doesn't fit in the rest of its row moves to the next one; when they
+    /// are placed in columns, a cell that spans columns gets a row of its
+    /// own. A span of 1 makes the cell an ordinary one again. Indices past
+    /// the last cell are ignored.
+    pub fn set_span(&mut self, index: usize, columns: usize) {
+        if index >= self.cells.len() {
+            return;
+        }
+        if columns > 1 {
+            self.spans.insert(index, columns);
+        } else {
+            self.spans.remove(&index);
+        }
+        self.relayout();
+    }
+
+    /// Requires the cells in the given range to be placed in the same
+    /// column, or in the same row if the cells are placed in rows, as with
+    /// [`Direction::LeftToRight`], and lays the grid out again.
+    ///
+    /// Layouts that split any of these ranges are passed over. If no layout
+    /// keeps them together and fits the width, the grid falls back to a
+    /// single column, like it does when a cell is too wide. The range is
+    /// clamped to the cells that exist.
+    pub fn keep_together(&mut self, range: Range<usize>) {
+        let len = self.cells.len();
+        self.kept_together
+            .push(range.start.min(len)..range.end.min(len));
+        self.relayout();
+    }
+
+    /// Sorts the cells with a comparison function and lays the grid out
+    /// again.
+    ///
+    /// The sort is stable, and the suffixes, depths, metadata and selection
+    /// move along with the cells. The index each cell had before sorting can
+    /// be looked up with [`Grid::original_index`]. In a grid with groups or
+    /// breaks, each group and section is sorted on its own.
+    pub fn sort_by(&mut self, mut compare: impl FnMut(&T, &T) -> Ordering) {
+        let mut permutation: Vec<usize> = (0..self.cells.len()).collect();
+        for (group, _) in self.groups() {
+            for section in self.sections(group) {
+                permutation[section].sort_by(|&a, &b| compare(&self.cells[a], &self.cells[b]));
+            }
+        }
+        self.permute(&permutation);
+    }
+
+    /// Sorts the cells with a key extraction function and lays the grid out
+    /// again, like [`Grid::sort_by`].
+    pub fn sort_by_key<K: Ord>(&mut self, mut key: impl FnMut(&T) -> K) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+
+    /// Lays several grids out with the same columns, so that they line up
+    /// when they are written one after another, like the listings of
+    /// several directories.
+    ///
+    /// The grids get the most columns with which each of them still fits
+    /// its width, and every column is as wide as the widest cell in that
+    /// column in any of the grids. The shared layouts are kept until a grid
+    /// is laid out again, for example by [`Grid::refit`].
+    pub fn share_column_widths(grids: &mut [Self]) {
+        let most_columns = (grids.iter())
+            .map(|grid| {
+                let widths = grid.layout_widths();
+                grid.most_columns(&widths).min(grid.cells.len())
+            })
+            .max()
+            .unwrap_or(0);
+
+        let layouts_in = |num_columns| {
+            let mut layouts: Vec<Layout> = (grids.iter())
+                .map(|grid| grid.layout_in_columns(num_columns))
+                .collect();
+            let columns = layouts.iter().map(|l| l.widths.len()).max().unwrap_or(0);
+            let mut shared = vec![0; columns];
+            for layout in &layouts {
+                for (shared, &width) in shared.iter_mut().zip(layout.widths.iter()) {
+                    *shared = (*shared).max(width);
+                }
+            }
+            for layout in &mut layouts {
+                let columns = layout.widths.len();
+                layout.widths.copy_from_slice(&shared[..columns]);
+            }
+            layouts
+        };
+
+        let layouts = (1..=most_columns)
+            .rev()
+            .map(layouts_in)
+            .find(|layouts| {
+                grids.iter().zip(layouts).all(|(grid, layout)| {
+                    layout.total_width(&grid.options.filling) <= grid.available_width
+                })
+            })
+            .unwrap_or_else(|| layouts_in(1));
+        for (grid, layout) in grids.iter_mut().zip(layouts) {
+            grid.layout = layout;
+            grid.plan = RenderPlan::new(grid);
+        }
+    }
+
+    /// The layout that was computed for this grid.
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// The number of terminal columns this display takes up, based on the separator
+    /// width and the number and width of the columns.
+    pub fn width(&self) -> usize {
+        self.gutter_width() + self.layout.total_width(&self.options.filling)
+    }
+
+    /// The number of rows this display takes up.
+    pub fn row_count(&self) -> usize {
+        self.layout.num_lines
+    }
+
+    /// The number of terminal columns that were allotted to the grid but
+    /// that it doesn't take up, or 0 if it overflows.
+    ///
+    /// This is room that is free for drawing something after the grid, such
+    /// as a border on its right.
+    pub fn remaining_width(&self) -> usize {
+        let used = self.layout.total_width(&self.options.filling);
+        self.available_width.saturating_sub(used)
+    }
+
+    /// The number of terminal columns that row `y` takes up as it is
+    /// printed, or `None` if there is no such row.
+    ///
+    /// This is usually narrower than [`Grid::width`], because the last cell
+    /// of a row is not padded, and a row can end before the last column.
+    /// For a row that spans several lines, this is the width of the widest
+    /// of them. Callers can use it to put an annotation right after each row.
+    pub fn row_width(&self, y: usize) -> Option<usize> {
+        if y >= self.layout.num_lines {
+            return None;
+        }
+
+        let mut row = String::new();
+        RowRenderer::new(self)
+            .write_row(&mut row, y)
+            .expect("writing to a String cannot fail");
+        let (stops, tab_size) = match &self.options.filling {
+            Filling::Tabs {
+                stops, tab_size, ..
+            } => (&stops[..], *tab_size),
+            _ => (&[][..], 0),
+        };
+        let line_ending = self.options.line_ending.as_str();
+        let width = if line_ending.is_empty() {
+            tabbed_width(&row, stops, tab_size)
+        } else {
+            (row.split(line_ending))
+                .map(|line| tabbed_width(line, stops, tab_size))
+                .max()
+                .unwrap_or(0)
+        };
+        Some(width)
+    }
+
+    /// The width of each column
+    pub fn column_widths(&self) -> &[usize] {
+        &self.layout.widths
+    }
+
+    /// The index and width of the widest cell, or `None` if the grid is
+    /// empty.
+    ///
+    /// The width is the one the cell was measured with, before it is
+    /// truncated or wrapped. If several cells are the widest, this is the
+    /// first of them.
+    pub fn widest_cell(&self) -> Option<(usize, usize)> {
+        widest(self.widths.iter().enumerate())
+    }
+
+    /// The index and width of the widest cell in column `x`, or `None` if
+    /// there is no such column.
+    ///
+    /// This is the cell that makes the column as wide as it is, unless the
+    /// grid shares its column widths with others or is laid out for a
+    /// percentile of the widths.
+    pub fn widest_in_column(&self, x: usize) -> Option<(usize, usize)> {
+        let column = self.columns().nth(x)?;
+        widest(column.map(|index| (index, self.widths.get(index))))
+    }
+
+    /// The row and column at which the cell with the given index was placed,
+    /// or `None` if there is no such cell.
+    pub fn cell_position(&self, index: usize) -> Option<(usize, usize)> {
+        self.layout.position(index)
+    }
+
+    /// The cell placed at the given row and column, or `None` if that
+    /// position is outside the grid or empty.
+    pub fn cell_at(&self, row: usize, column: usize) -> Option<&T> {
+        self.layout
+            .index_at(row, column)
+            .map(|index| &self.cells[index])
+    }
+
+    /// The metadata of the cell with the given index, or `None` if the index
+    /// is out of bounds or the grid was created without metadata.
+    pub fn meta(&self, index: usize) -> Option<&M> {
+        self.meta.get(index)
+    }
+
+    /// The metadata of the cell at the given row and column, or `None` if
+    /// that position is outside the grid or empty, or the grid was created
+    /// without metadata.
+    pub fn meta_at(&self, row: usize, column: usize) -> Option<&M> {
+        self.meta(self.layout.index_at(row, column)?)
+    }
+
+    /// The index the cell with the given index had before the grid was
+    /// sorted, or `None` if there is no such cell.
+    ///
+    /// Cells added after sorting continue the numbering from the highest
+    /// original index. For a grid that was never sorted, this is the index
+    /// itself.
+    pub fn original_index(&self, index: usize) -> Option<usize> {
+        if index >= self.cells.len() {
+            return None;
+        }
+        Some(self.order.get(index).copied().unwrap_or(index))
+    }
+
+    /// The index the cell at the given row and column had before the grid
+    /// was sorted, or `None` if that position is outside the grid or empty.
+    pub fn original_index_at(&self, row: usize, column: usize) -> Option<usize> {
+        self.original_index(self.layout.index_at(row, column)?)
+    }
+
+    /// The header written in the given row, or `None` if that row holds
+    /// cells or is outside the grid.
+    ///
+    /// Only grids created with [`Grid::new_grouped`] have headers.
+    pub fn header_at(&self, row: usize) -> Option<&str> {
+        let header = self.layout.header_at(row)?;
+        self.headers.get(header).map(|(_, header)| header.as_str())
+    }
+
+    /// Returns an iterator over the rows of the grid, each of which is an
+    /// iterator over the indices of the cells in that row.
+    ///
+    /// This is a shorthand for `grid.layout().rows()`.
+    pub fn rows(&self) -> Rows<'_> {
+        self.layout.rows()
+    }
+
+    /// Returns an iterator over the columns of the grid, each of which is an
+    /// iterator over the indices of the cells in that column.
+    ///
+    /// This is a shorthand for `grid.layout().columns()`.
+    pub fn columns(&self) -> Columns<'_> {
+        self.layout.columns()
+    }
+
+    /// The cells as they are arranged in the layout, as a list of rows that
+    /// each hold every column, with `None` for the empty positions.
+    ///
+    /// Unlike [`Grid::rows`], this includes the empty positions at the end
+    /// of the last row or column, so every row is as long as the others.
+    /// A cell that spans several columns is in each of them. This is useful
+    /// for rendering the grid into widgets of one's own.
+    pub fn as_matrix(&self) -> Vec<Vec<Option<&T>>> {
+        let num_columns = self.layout.widths.len();
+        (0..self.layout.num_lines)
+            .map(|y| {
+                (0..num_columns)
+                    .map(|x| self.layout.index_at(y, x).map(|index| &self.cells[index]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Reports whether the grid is wider than the width it was given.
+    ///
+    /// This happens when a cell is wider than the available width on its
+    /// own. The grid then falls back to a single column, which still
+    /// overflows. Returns `None` if the grid fits.
+    pub fn overflow(&self) -> Option<Overflow> {
+        let width = self.layout.total_width(&self.options.filling);
+        if width <= self.available_width {
+            return None;
+        }
+
+        let offending_cells = (self.widths.iter().enumerate())
+            .filter(|&(_, cell_width)| cell_width > self.available_width)
+            .map(|(index, _)| index)
+            .collect();
+
+        Some(Overflow {
+            exceeded_by: width - self.available_width,
+            offending_cells,
+        })
+    }
+
+    /// Returns whether this display takes up as many columns as were allotted
+    /// to it.
+    ///
+    /// It’s possible to construct tables that don’t actually use up all the
+    /// columns that they could, such as when there are more columns than
+    /// cells! In this case, a column would have a width of zero. This just
+    /// checks for that.
+    pub fn is_complete(&self) -> bool {
+        self.layout.widths.iter().all(|&x| x > 0)
+    }
+
+    /// Selects the cell with the given index, so that it is highlighted
+    /// with [`GridOptions::selection_style`] when the grid is rendered.
+    ///
+    /// The highlight covers the padding of the cell as well, so the whole
+    /// column is highlighted. Indices past the last cell are ignored.
+    pub fn select(&mut self, index: usize) {
+        if index < self.cells.len() {
+            self.selected.insert(index);
+        }
+    }
+
+    /// Removes the cell with the given index from the selection.
+    pub fn deselect(&mut self, index: usize) {
+        self.selected.remove(&index);
+    }
+
+    /// Removes all cells from the selection.
+    pub fn clear_selection(&mut self) {
+        self.selected.clear();
+    }
+
+    /// Whether the cell with the given index is selected.
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selected.contains(&index)
+    }
+
+    /// Writes the grid to the given writer.
+    ///
+    /// This produces the same output as the [`Display`](fmt::Display)
+    /// implementation, but streams it to the writer instead of building the
+    /// whole output in memory first, as `to_string` and [`render`](Self::render)
+    /// would.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "{self}")
+    }
+
+    /// Writes every row of the grid without escape sequences, passing the
+    /// contents of the cells to the writer separately so that it can style
+    /// them.
+    #[cfg(any(feature = "crossterm", feature = "termcolor"))]
+    fn write_unstyled(&self, f: &mut impl RowWriter) -> fmt::Result {
+        let renderer = RowRenderer::unstyled(self);
+        let line_ending = self.options.line_ending.as_str();
+        for y in 0..self.layout.num_lines {
+            renderer.write_row(f, y)?;
+            if y + 1 < self.layout.num_lines || self.options.final_line_ending {
+                f.write_str(line_ending)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the whole grid into a string.
+    ///
+    /// This gives the same string as `to_string`, but reserves room for the
+    /// output up front, estimated from the widths of the columns and the
+    /// number of rows, instead of growing the string as it goes. That saves
+    /// copying the output over and over for grids that are megabytes long.
+    pub fn render(&self) -> String {
+        let mut out = String::with_capacity(self.estimated_len());
+        fmt::write(&mut out, format_args!("{self}")).expect("writing to a String cannot fail");
+        out
+    }
+
+    /// The number of bytes the rendered grid is expected to take up, which
+    /// is exact for ASCII text padded with spaces.
+    fn estimated_len(&self) -> usize {
+        let line_ending = self.options.line_ending.as_str().len();
+        (self.width() + line_ending).saturating_mul(self.layout.num_lines)
+    }
+
+    /// Renders only column `x` of the grid, with the cell from each row on a
+    /// line of its own, padded to the width of the column.
+    ///
+    /// A row that has no cell in the column gets a blank line, so that the
+    /// lines stay level with the rows of the grid. This is useful for UIs
+    /// that draw each column into a pane of its own. Returns an empty string
+    /// if there is no such column.
+    pub fn render_column(&self, x: usize) -> String {
+        let mut out = String::new();
+        if x < self.layout.widths.len() {
+            RowRenderer::new(self)
+                .write_column(&mut out, x)
+                .expect("writing to a String cannot fail");
+        }
+        out
+    }
+
+    /// Returns an iterator over the rendered lines of the grid.
+    ///
+    /// Each line is rendered only when it is requested, so the full output
+    /// never needs to be held in memory. The lines do not include the
+    /// trailing newline. With [`OverflowPolicy::Wrap`], each item is a whole
+    /// row of the grid, which can span several lines.
+    pub fn lines(&self) -> Lines<'_, T, M> {
+        Lines {
+            renderer: RowRenderer::new(self),
+            next: 0,
+        }
+    }
+
+    /// Renders only the given range of rows of the grid.
+    ///
+    /// The layout is the same as for the full grid, so the rows line up with
+    /// the rest of the grid. Rows past the end of the grid are ignored. This
+    /// is useful for showing a scrolling window into a large grid.
+    pub fn render_rows(&self, rows: impl RangeBounds<usize>) -> RowRange<'_, T, M> {
+        let num_lines = self.layout.num_lines;
+        let start = match rows.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match rows.end_bound() {
+            Bound::Included(&n) => n.saturating_add(1),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => num_lines,
+        };
+        let end = end.min(num_lines);
+
+        RowRange {
+            grid: self,
+            rows: start.min(end)..end,
+        }
+    }
+
+    /// Splits the grid into pages of at most `height` rows each.
+    ///
+    /// The returned [`Pages`] is an iterator over the pages, which can be
+    /// displayed one at a time, for example to pause after each screenful.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `height` is zero.
+    pub fn pages(&self, height: usize) -> Pages<'_, T, M> {
+        assert!(height > 0, "page height must be greater than zero");
+        Pages {
+            grid: self,
+            height,
+            current: 0,
+        }
+    }
+
+    /// Creates a window of `height` rows that can be scrolled through the
+    /// grid, starting at the top.
+    ///
+    /// Unlike [`Grid::render_rows`], the returned [`RowWindow`] keeps the
+    /// rows it rendered, so moving it only renders the rows that come into
+    /// view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `height` is zero.
+    pub fn row_window(&self, height: usize) -> RowWindow<'_, T, M> {
+        assert!(height > 0, "window height must be greater than zero");
+        RowWindow {
+            grid: self,
+            height,
+            offset: 0,
+            rows: VecDeque::new(),
+        }
+    }
+
+    /// Renders the grid as an HTML `<table>`, using the same assignment of
+    /// cells to rows and columns as the terminal output.
+    ///
+    /// Cell contents are escaped, and no padding or separators are emitted;
+    /// alignment is left to the browser.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from("<table>\n");
+        for row in self.rows() {
+            html.push_str("<tr>");
+            for num in row {
+                html.push_str("<td>");
+                escape_html(&mut html, &self.cells[num].text());
+                html.push_str("</td>");
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+
+    /// Exports the grid as delimiter-separated values, one line per row.
+    ///
+    /// The fields are not padded, so `','` gives CSV and `'\t'` gives TSV with
+    /// exactly the rows and columns of the terminal output. Fields containing
+    /// the delimiter, a double quote or a line break are quoted as in CSV.
+    pub fn to_delimited(&self, delimiter: char) -> String {
+        let mut out = String::new();
+        for row in self.rows() {
+            for (x, num) in row.enumerate() {
+                if x > 0 {
+                    out.push(delimiter);
+                }
+                push_field(&mut out, &self.cells[num].text(), delimiter);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The text of the cell with the given index as it should be displayed,
+    /// before it is padded or truncated.
+    fn display_text(&self, index: usize) -> Cow<'_, str> {
+        match self.cells[index].as_str() {
+            Some(cell) => self.options.display_text(cell),
+            None => self.cells[index].text(),
+        }
+    }
+
+    /// The suffix of the cell with the given index, or `""` if it has none.
+    fn suffix(&self, index: usize) -> &str {
+        self.suffixes.get(index).map_or("", String::as_str)
+    }
+
+    /// The width of the indentation of the cell with the given index.
+    fn indent(&self, index: usize) -> usize {
+        self.depths
+            .get(index)
+            .map_or(0, |depth| depth * self.options.indent_width)
+    }
+
+    /// The width of the suffix of the cell with the given index, if it
+    /// counts towards the width of the cell.
+    fn counted_suffix_width(&self, index: usize) -> usize {
+        if self.options.exclude_suffixes {
+            0
+        } else {
+            ansi_width(self.suffix(index))
+        }
+    }
+
+    /// Recomputes the layout from the options and the measured cell widths.
+    fn relayout(&mut self) {
+        self.percentile_width = self.percentile_width();
+        let total_width = self.options.width.resolve();
+        self.available_width = total_width;
+        self.layout = self.search_layout();
+
+        // The gutter takes width away from the cells, which can lead to more
+        // rows and therefore a wider gutter, so repeat until it settles.
+        let mut gutter_width = 0;
+        while gutter_width != self.gutter_width() {
+            gutter_width = self.gutter_width();
+            self.available_width = total_width.saturating_sub(gutter_width);
+            self.layout = self.search_layout();
+        }
+        self.plan = RenderPlan::new(self);
+    }
+
+    /// Moves the cell at index `permutation[i]` to index `i`, along with
+    /// everything stored per cell, and lays the grid out again.
+    fn permute(&mut self, permutation: &[usize]) {
+        fn reorder<V>(values: &mut Vec<V>, permutation: &[usize]) {
+            if values.is_empty() {
+                return;
+            }
+            let mut old: Vec<Option<V>> = core::mem::take(values).into_iter().map(Some).collect();
+            *values = (permutation.iter())
+                .map(|&i| old[i].take().expect("a permutation uses each index once"))
+                .collect();
+        }
+
+        if self.order.is_empty() {
+            self.order = (0..self.cells.len()).collect();
+        }
+        reorder(&mut self.cells, permutation);
+        reorder(&mut self.meta, permutation);
+        reorder(&mut self.suffixes, permutation);
+        reorder(&mut self.depths, permutation);
+        self.widths.reorder(permutation);
+        reorder(&mut self.order, permutation);
+        self.selected = (permutation.iter().enumerate())
+            .filter(|(_, old)| self.selected.contains(old))
+            .map(|(new, _)| new)
+            .collect();
+        self.spans = (permutation.iter().enumerate())
+            .filter_map(|(new, old)| Some((new, *self.spans.get(old)?)))
+            .collect();
+        self.relayout();
+    }
+
+    /// The width of the line numbers at the start of each row, including
+    /// the space after them.
+    fn gutter_width(&self) -> usize {
+        match self.layout.num_lines {
+            0 => 0,
+            _ if !self.options.line_numbers => 0,
+            n => n.ilog10() as usize + 2,
+        }
+    }
+
+    /// Searches for the layout that fits the available width best.
+    fn search_layout(&self) -> Layout {
+        if let Some(split) = self.pair_split {
+            return self.pair_layout(split);
+        }
+        if !self.headers.is_empty() || !self.breaks.is_empty() || !self.spans.is_empty() {
+            return self.search_group_layout();
+        }
+        match (self.options.compat, self.options.width) {
+            (_, Width::Unlimited) | (Compat::None, _) => {}
+            (Compat::GnuLs, _) => return self.gnu_ls_layout(),
+            (Compat::BsdLs, _) => return self.bsd_ls_layout(),
+        }
+        let mut layout = match self.options.width {
+            // There is nothing to search for: everything goes on one row.
+            Width::Unlimited if self.cells.is_empty() => {
+                self.layout_with(0, ColumnWidths::default())
+            }
+            Width::Unlimited if self.options.strategy != Strategy::Balanced => {
+                self.compute_dimensions(&self.widths, 1, self.cells.len())
+            }
+            _ if self.options.strategy == Strategy::Greedy => self.greedy_layout(),
+            _ if self.clamps_cells() => {
+                let width = self.clamped_width().unwrap_or(self.widest_cell_width);
+                self.width_dimensions(&self.layout_widths(), width, self.available_width)
+                    .unwrap_or_else(|| {
+                        self.layout_with(self.cells.len(), ColumnWidths::filled(width, 1))
+                    })
+            }
+            _ => self
+                .width_dimensions(&self.widths, self.widest_cell_width, self.available_width)
+                .unwrap_or_else(|| {
+                    self.layout_with(
+                        self.cells.len(),
+                        ColumnWidths::filled(self.widest_cell_width, 1),
+                    )
+                }),
+        };
+        if self.options.balance_columns {
+            layout = self.balance_columns(layout);
+        }
+        if self.options.avoid_orphans {
+            layout = self.avoid_orphan(layout);
+        }
+        layout
+    }
+
+    /// Replaces a layout that goes along the rows and leaves a single cell
+    /// on its last row with one that has a column fewer or a row more, if
+    /// that avoids the lonely cell and still fits.
+    fn avoid_orphan(&self, layout: Layout) -> Layout {
+        let num_cells = self.cells.len();
+        let num_columns = layout.widths.len();
+        let in_columns = matches!(
+            layout.direction,
+            Direction::TopToBottom | Direction::BottomToTop
+        );
+        if in_columns || layout.placement.is_some() || num_columns < 2 {
+            return layout;
+        }
+        if num_cells % num_columns != 1 {
+            return layout;
+        }
+
+        let widths = self.layout_widths();
+        let fewer_columns = num_columns - 1;
+        let more_lines = div_ceil(num_cells, layout.num_lines + 1);
+        [fewer_columns, more_lines]
+            .into_iter()
+            .filter(|&columns| num_cells % columns != 1)
+            .map(|columns| self.compute_dimensions(&widths, div_ceil(num_cells, columns), columns))
+            .find(|candidate| {
+                candidate.total_width(&self.options.filling) <= self.available_width
+                    && self.keeps_together(candidate)
+            })
+            .unwrap_or(layout)
+    }
+
+    /// Lays the cells out in as many columns as the widest cell fits into
+    /// side by side, without searching for a layout with fewer lines.
+    fn greedy_layout(&self) -> Layout {
+        let widths = self.layout_widths();
+        let num_cells = widths.len();
+        let Some(widest) = widths.iter().max() else {
+            return self.layout_with(0, ColumnWidths::default());
+        };
+
+        let gap = self.options.filling.gap_width(0);
+        let num_columns =
+            ((self.available_width + gap) / (widest + gap).max(1)).clamp(1, num_cells);
+        let num_lines = div_ceil(num_cells, num_columns);
+        let num_columns = match self.options.direction {
+            Direction::TopToBottom | Direction::BottomToTop => div_ceil(num_cells, num_lines),
+            _ => num_columns,
+        };
+
+        let layout = self.compute_dimensions(&widths, num_lines, num_columns);
+        let fits = layout.total_width(&self.options.filling) <= self.available_width;
+        if fits && self.keeps_together(&layout) {
+            layout
+        } else {
+            self.width_dimensions(&widths, widest, self.available_width)
+                .unwrap_or_else(|| self.layout_with(num_cells, ColumnWidths::filled(widest, 1)))
+        }
+    }
+
+    /// Spreads the cells of a layout that goes down the columns evenly over
+    /// its columns, so that they differ by one cell at most, if that still
+    /// fits.
+    fn balance_columns(&self, layout: Layout) -> Layout {
+        let num_cells = self.cells.len();
+        let num_columns = layout.widths.len();
+        let upwards = match layout.direction {
+            Direction::TopToBottom => false,
+            Direction::BottomToTop => true,
+            _ => return layout,
+        };
+        if num_columns < 2 || layout.placement.is_some() {
+            return layout;
+        }
+
+        // The first columns take one more cell each until the rest divide
+        // evenly.
+        let num_lines = div_ceil(num_cells, num_columns);
+        let longer = num_cells % num_columns;
+        let mut positions = Vec::with_capacity(num_cells);
+        for column in 0..num_columns {
+            let height = num_cells / num_columns + usize::from(column < longer);
+            for row in 0..height {
+                let row = if upwards { num_lines - 1 - row } else { row };
+                positions.push((row, column));
+            }
+        }
+        if layout.reversed {
+            positions.reverse();
+        }
+
+        let widths = self.layout_widths();
+        let mut column_widths = ColumnWidths::filled(0, num_columns);
+        let mut table = vec![None; num_lines * num_columns];
+        for (index, &(row, column)) in positions.iter().enumerate() {
+            column_widths[column] = column_widths[column].max(widths.get(index));
+            table[row * num_columns + column] = Some(index);
+        }
+
+        let balanced = Layout {
+            num_lines,
+            widths: column_widths,
+            placement: Some(Placement {
+                positions,
+                table,
+                headers: Vec::new(),
+            }),
+            ..layout
+        };
+        let fits = balanced.total_width(&self.options.filling) <= self.available_width;
+        if fits && self.keeps_together(&balanced) {
+            balanced
+        } else {
+            layout
+        }
+    }
+
+    /// Searches for the layout with the most columns that fits the groups of
+    /// cells into the available width.
+    fn search_group_layout(&self) -> Layout {
+        let widths = self.layout_widths();
+        let groups = self.groups();
+        let longest = groups.iter().map(|(range, _)| range.len()).max();
+        let longest = longest.unwrap_or(0).max(1);
+        if self.options.width == Width::Unlimited {
+            return self.group_layout(&widths, &groups, longest);
+        }
+
+        (1..=self.most_columns(&widths).min(longest))
+            .rev()
+            .map(|num_columns| self.group_layout(&widths, &groups, num_columns))
+            .find(|layout| {
+                layout.total_width(&self.options.filling) <= self.available_width
+                    && self.keeps_together(layout)
+            })
+            .unwrap_or_else(|| self.group_layout(&widths, &groups, 1))
+    }
+
+    /// The most columns that could fit into the available width, which is
+    /// as many as the narrowest cells side by side.
+    fn most_columns(&self, widths: &CellWidths) -> usize {
+        SortedWidths::new(widths).most_narrowest(&self.options.filling, self.available_width)
+    }
+
+    /// Lays the cells out in the given number of columns, with as few lines
+    /// as that allows.
+    fn layout_in_columns(&self, num_columns: usize) -> Layout {
+        let widths = self.layout_widths();
+        if self.layout.placement.is_some() {
+            return self.group_layout(&widths, &self.groups(), num_columns);
+        }
+        if self.cells.is_empty() {
+            return self.layout_with(0, ColumnWidths::default());
+        }
+
+        let num_lines = div_ceil(self.cells.len(), num_columns);
+        let num_columns = match self.options.direction {
+            Direction::TopToBottom | Direction::BottomToTop => {
+                div_ceil(self.cells.len(), num_lines)
+            }
+            _ => num_columns,
+        };
+        self.compute_dimensions(&widths, num_lines, num_columns)
+    }
+
+    /// Lays the cells out in two columns, with the first `split` cells on
+    /// the left and the rest on the right.
+    fn pair_layout(&self, split: usize) -> Layout {
+        let mut positions = Vec::with_capacity(self.cells.len());
+        positions.extend((0..split).map(|row| (row, 0)));
+        positions.extend((0..self.cells.len() - split).map(|row| (row, 1)));
+        let num_lines = split.max(self.cells.len() - split);
+
+        let mut table = vec![None; num_lines * 2];
+        for (index, &(row, column)) in positions.iter().enumerate() {
+            table[row * 2 + column] = Some(index);
+        }
+
+        let mut left = self.widths.range_max(0..split);
+        let mut right = self.widths.range_max(split..self.widths.len());
+        let room = self
+            .available_width
+            .saturating_sub(self.options.filling.gap_width(0));
+        if self.options.width != Width::Unlimited && left + right > room {
+            // Narrow the wider column first, and both once they are equal.
+            left = left.min((room / 2).max(room.saturating_sub(right)));
+            right = right.min(room - left);
+        }
+
+        Layout {
+            direction: Direction::LeftToRight,
+            reversed: false,
+            num_cells: self.cells.len(),
+            num_lines,
+            widths: [left, right].into_iter().collect(),
+            placement: Some(Placement {
+                positions,
+                table,
+                headers: Vec::new(),
+            }),
+        }
+    }
+
+    /// The ranges of the indices of the cells in each group, with the index
+    /// of the group's header. Cells before the first header form a group
+    /// without one.
+    fn groups(&self) -> Vec<(Range<usize>, Option<usize>)> {
+        let mut groups = Vec::with_capacity(self.headers.len() + 1);
+        let mut start = 0;
+        let mut header = None;
+        for (i, &(first, _)) in self.headers.iter().enumerate() {
+            if header.is_some() || first > start {
+                groups.push((start..first, header));
+            }
+            start = first;
+            header = Some(i);
+        }
+        if header.is_some() || start < self.cells.len() {
+            groups.push((start..self.cells.len(), header));
+        }
+        groups
+    }
+
+    /// Splits a group of cells into the sections between its breaks.
+    fn sections(&self, group: Range<usize>) -> Vec<Range<usize>> {
+        // The two sides of a grid that is shown side by side are sections
+        // as well.
+        let mut breaks: Vec<usize> = self
+            .breaks
+            .range(group.start + 1..group.end)
+            .copied()
+            .collect();
+        if let Some(split) = self
+            .pair_split
+            .filter(|&split| split > group.start && split < group.end)
+        {
+            let i = breaks.partition_point(|&index| index < split);
+            breaks.insert(i, split);
+        }
+
+        let mut sections = Vec::new();
+        let mut start = group.start;
+        for index in breaks {
+            sections.push(start..index);
+            start = index;
+        }
+        sections.push(start..group.end);
+        sections
+    }
+
+    /// Lays the groups of cells out one below the other, each in the given
+    /// number of columns and preceded by its header.
+    ///
+    /// Within a group, each section starts a new row when the cells are
+    /// placed in rows, and a new column when they are placed in columns.
+    fn group_layout(
+        &self,
+        widths: &CellWidths,
+        groups: &[(Range<usize>, Option<usize>)],
+        num_columns: usize,
+    ) -> Layout {
+        let longest = groups.iter().map(|(range, _)| range.len()).max();
+        let mut placer = Placer {
+            direction: self.options.direction,
+            num_columns: num_columns.min(longest.unwrap_or(0)).max(1),
+            spans: &self.spans,
+            positions: vec![(0, 0); self.cells.len()],
+            row: 0,
+            columns: 1,
+        };
+
+        let mut headers = Vec::new();
+        let reversed = self.options.reverse;
+        let ordered: Vec<_> = if reversed {
+            groups.iter().rev().collect()
+        } else {
+            groups.iter().collect()
+        };
+        for (range, header) in ordered {
+            if let Some(header) = *header {
+                headers.push((placer.row, header));
+                placer.row += 1;
+            }
+
+            let mut sections: Vec<Vec<usize>> = (self.sections(range.clone()).into_iter())
+                .map(|section| {
+                    if reversed {
+                        section.rev().collect()
+                    } else {
+                        section.collect()
+                    }
+                })
+                .collect();
+            if reversed {
+                sections.reverse();
+            }
+            placer.place_group(&sections);
+        }
+
+        let Placer {
+            num_columns,
+            positions,
+            row: num_lines,
+            columns,
+            ..
+        } = placer;
+        let span = |index| {
+            self.spans
+                .get(&index)
+                .map_or(1, |&span| span.min(num_columns))
+        };
+
+        let mut column_widths = ColumnWidths::filled(0, columns);
+        let mut table = vec![None; num_lines * columns];
+        for (index, &(r, c)) in positions.iter().enumerate() {
+            let span = span(index);
+            if span == 1 {
+                column_widths[c] = column_widths[c].max(widths.get(index));
+            }
+            for slot in &mut table[r * columns + c..r * columns + c + span] {
+                *slot = Some(index);
+            }
+        }
+
+        // Widen the last column of a span that is too narrow for its cell.
+        for &index in self.spans.keys() {
+            let (_, c) = positions[index];
+            let last = c + span(index) - 1;
+            let gaps = (c..last).map(|gap| self.options.filling.gap_width(gap));
+            let available = column_widths[c..=last].iter().sum::<usize>() + gaps.sum::<usize>();
+            column_widths[last] += widths.get(index).saturating_sub(available);
+        }
+
+        Layout {
+            direction: self.options.direction,
+            reversed,
+            num_cells: self.cells.len(),
+            num_lines,
+            widths: column_widths,
+            placement: Some(Placement {
+                positions,
+                table,
+                headers,
+            }),
+        }
+    }
+
+    /// Whether the layout places each range of cells that is kept together
+    /// in a single column, or a single row if the cells are placed in rows.
+    fn keeps_together(&self, layout: &Layout) -> bool {
+        let in_columns = matches!(
+            layout.direction,
+            Direction::TopToBottom | Direction::BottomToTop
+        );
+        self.kept_together.iter().all(|range| {
+            let mut lines = range.clone().map(|index| {
+                let (row, column) = layout.place(index);
+                if in_columns {
+                    column
+                } else {
+                    row
+                }
+            });
+            let first = lines.next();
+            lines.all(|line| Some(line) == first)
+        })
+    }
+
+    /// Whether cells have to be truncated or wrapped to fit the available
+    /// width or the width percentile.
+    fn clamps_cells(&self) -> bool {
+        self.clamped_width().is_some()
+    }
+
+    /// The width that the widest cells are cut down or wrapped to, if any
+    /// are.
+    fn clamped_width(&self) -> Option<usize> {
+        let overflows = self.options.overflow != OverflowPolicy::Collapse
+            && self.options.width != Width::Unlimited
+            && self.widest_cell_width > self.available_width;
+        match (self.percentile_width, overflows) {
+            (Some(width), true) => Some(width.min(self.available_width)),
+            (Some(width), false) => Some(width),
+            (None, true) => Some(self.available_width),
+            (None, false) => None,
+        }
+    }
+
+    /// The width of the cells at the width percentile, if that is narrower
+    /// than the widest cell.
+    fn percentile_width(&self) -> Option<usize> {
+        let percentile = usize::from(self.options.width_percentile?);
+        let mut widths: Vec<usize> = self.widths.iter().collect();
+        if widths.is_empty() {
+            return None;
+        }
+        let rank = div_ceil(percentile * widths.len(), 100).clamp(1, widths.len());
+        let (_, &mut width, _) = widths.select_nth_unstable(rank - 1);
+        (width < self.widest_cell_width).then_some(width)
+    }
+
+    /// The widths to lay the cells out with.
+    ///
+    /// When cells are clamped, the oversized cells are taken to be exactly as
+    /// wide as they'll be cut down or wrapped to.
+    fn layout_widths(&self) -> Cow<'_, CellWidths> {
+        match self.clamped_width() {
+            Some(width) => Cow::Owned(self.widths.clamped(width)),
+            None => Cow::Borrowed(&self.widths),
+        }
+    }
+
+    /// A layout for this grid's cells with the given shape.
+    fn layout_with(&self, num_lines: usize, widths: ColumnWidths) -> Layout {
+        Layout {
+            direction: self.options.direction,
+            reversed: self.options.reverse,
+            num_cells: self.cells.len(),
+            num_lines,
+            widths,
+            placement: None,
+        }
+    }
+
+    fn compute_dimensions(
+        &self,
+        widths: &CellWidths,
+        num_lines: usize,
+        num_columns: usize,
+    ) -> Layout {
+        let mut layout = self.layout_with(num_lines, ColumnWidths::filled(0, num_columns));
+        for (index, cell_width) in widths.iter().enumerate() {
+            let (_, column) = layout.place(index);
+            if cell_width > layout.widths[column] {
+                layout.widths[column] = cell_width;
+            }
+        }
+
+        layout
+    }
+
+    /// The most lines that the grid could need, which is when it has as
+    /// many columns as the widest cells side by side need.
+    fn theoretical_max_num_lines(&self, sorted: &SortedWidths, maximum_width: usize) -> usize {
+        match sorted.most_widest(&self.options.filling, maximum_width) {
+            0 => self.cells.len(),
+            columns => div_ceil(self.cells.len(), columns),
+        }
+    }
+
+    /// Searches for the layout with the fewest lines that fits the given cell
+    /// widths into `maximum_width`.
+    fn width_dimensions(
+        &self,
+        widths: &CellWidths,
+        widest_cell_width: usize,
+        maximum_width: usize,
+    ) -> Option<Layout> {
+        if widest_cell_width > maximum_width {
+            // Largest cell is wider than maximum width; it is impossible to fit.
+            return None;
+        }
+
+        if self.cells.is_empty() {
+            return Some(self.layout_with(0, ColumnWidths::default()));
+        }
+
+        if self.cells.len() == 1 {
+            let cell_widths = widths.get(0);
+            return Some(self.layout_with(1, ColumnWidths::filled(cell_widths, 1)));
+        }
+
+        let sorted = SortedWidths::new(widths);
+        let theoretical_max_num_lines = self.theoretical_max_num_lines(&sorted, maximum_width);
+        let balanced = self.options.strategy == Strategy::Balanced;
+        if theoretical_max_num_lines == 1 && !balanced {
+            // This if—statement is necessary for the function to work correctly
+            // for small inputs.
+            let dimensions = self.compute_dimensions(widths, 1, widths.len());
+            if self.keeps_together(&dimensions) {
+                return Some(dimensions);
+            }
+        }
+
+        // With as many lines as there are cells up to the end of a range that
+        // is kept together, the whole range fits in the first column.
+        let kept_end = self.kept_together.iter().map(|range| range.end).max();
+        let mut max_num_lines = theoretical_max_num_lines.max(kept_end.unwrap_or(0));
+        if balanced {
+            // The squarest layout has about as many lines as columns, which
+            // may be more lines than are needed.
+            let square = ceil_sqrt(self.cells.len());
+            max_num_lines = max_num_lines.max(square);
+        }
+
+        let maxima = self.column_maxima(widths);
+        let maxima = maxima.as_ref();
+
+        // Only the fewest lines matter for the other strategies, so the
+        // candidates don't need to be ranked against each other.
+        if !balanced && self.kept_together.is_empty() {
+            let layout = self.fewest_lines_dimensions(
+                widths,
+                maxima,
+                &sorted,
+                max_num_lines,
+                maximum_width,
+            )?;
+            return Some(match self.options.strategy {
+                Strategy::MinimizeWidth => self.narrowest_with_lines(widths, layout, maximum_width),
+                _ => layout,
+            });
+        }
+
+        // Instead of numbers of columns, try to find the fewest number of *lines*
+        // that the output will fit in.
+        let mut smallest_dimensions_yet = None;
+        let mut best_key = None;
+        for num_lines in (1..=max_num_lines).rev() {
+            // The number of columns is the number of cells divided by the number
+            // of lines, *rounded up*.
+            let num_columns = div_ceil(self.cells.len(), num_lines);
+
+            // Early abort: if there are so many columns that the width of the
+            // *column separators* is bigger than the width of the screen, then
+            // don’t even try to tabulate it.
+            // This is actually a necessary check, because the width is stored as
+            // a usize, and making it go negative makes it huge instead, but it
+            // also serves as a speed-up.
+            let total_separator_width = self.options.filling.separators_width(num_columns);
+            if maximum_width < total_separator_width {
+                continue;
+            }
+
+            // Remove the separator width from the available space.
+            let adjusted_width = maximum_width - total_separator_width;
+
+            // Even the narrowest columns that this shape allows are too wide,
+            // and with fewer lines they only get wider.
+            if sorted.narrowest_total(num_lines, num_columns) > adjusted_width {
+                break;
+            }
+
+            let potential_dimensions =
+                self.candidate_dimensions(widths, maxima, num_lines, num_columns);
+            if potential_dimensions.widths.iter().sum::<usize>() > adjusted_width {
+                break;
+            }
+            if !self.keeps_together(&potential_dimensions) {
+                continue;
+            }
+
+            // Later candidates have fewer lines and more columns, so they win
+            // ties unless fewer columns are preferred.
+            let key = self.strategy_key(&potential_dimensions);
+            let better = match best_key {
+                None => true,
+                Some(best_key) if self.options.prefer_fewer_columns => key < best_key,
+                Some(best_key) => key <= best_key,
+            };
+            if better {
+                best_key = Some(key);
+                smallest_dimensions_yet = Some(potential_dimensions);
+            }
+        }
+
+        match smallest_dimensions_yet {
+            Some(layout) if self.options.strategy == Strategy::MinimizeWidth => {
+                Some(self.narrowest_with_lines(widths, layout, maximum_width))
+            }
+            layout => layout,
+        }
+    }
+
+    /// The layout with `num_lines` lines and as few columns as that allows,
+    /// if it fits into `maximum_width`.
+    fn fitting_dimensions(
+        &self,
+        widths: &CellWidths,
+        maxima: Option<&RangeMax<'_>>,
+        sorted: &SortedWidths,
+        num_lines: usize,
+        maximum_width: usize,
+    ) -> Option<Layout> {
+        let num_columns = div_ceil(self.cells.len(), num_lines);
+        let total_separator_width = self.options.filling.separators_width(num_columns);
+        let adjusted_width = maximum_width.checked_sub(total_separator_width)?;
+        if sorted.narrowest_total(num_lines, num_columns) > adjusted_width {
+            return None;
+        }
+        let layout = self.candidate_dimensions(widths, maxima, num_lines, num_columns);
+        (layout.widths.iter().sum::<usize>() <= adjusted_width).then_some(layout)
+    }
+
+    /// A way to find the widest cell in each column without going through
+    /// all the cells, if the cells go down the columns, where each column
+    /// holds a run of consecutive cells.
+    fn column_maxima<'w>(&self, widths: &'w CellWidths) -> Option<RangeMax<'w>> {
+        match self.options.direction {
+            Direction::TopToBottom | Direction::BottomToTop => Some(RangeMax::new(widths)),
+            _ => None,
+        }
+    }
+
+    /// The layout with the given shape, like [`Grid::compute_dimensions`],
+    /// but only looking at the widest cell of each column if `maxima` is
+    /// given.
+    fn candidate_dimensions(
+        &self,
+        widths: &CellWidths,
+        maxima: Option<&RangeMax<'_>>,
+        num_lines: usize,
+        num_columns: usize,
+    ) -> Layout {
+        let Some(maxima) = maxima else {
+            return self.compute_dimensions(widths, num_lines, num_columns);
+        };
+
+        let num_cells = self.cells.len();
+        let column_widths = (0..num_columns)
+            .map(|column| {
+                let start = (column * num_lines).min(num_cells);
+                let end = (start + num_lines).min(num_cells);
+                if self.options.reverse {
+                    maxima.max(num_cells - end..num_cells - start)
+                } else {
+                    maxima.max(start..end)
+                }
+            })
+            .collect();
+        self.layout_with(num_lines, column_widths)
+    }
+
+    /// Searches for the fewest lines that fit, starting from `max_num_lines`
+    /// and stopping at the first number of lines that doesn't fit, just like
+    /// trying every number of lines in turn.
+    ///
+    /// Fewer lines usually make the columns wider, but not always: a number
+    /// of lines that doesn't fit can be followed by a smaller one that does.
+    /// Jumping ahead could land on either side of the first one that doesn't
+    /// fit, so the numbers of lines are tried one after the other. Only when
+    /// the cells go along the rows are some of them skipped, because the
+    /// widths of the columns there only depend on the number of columns,
+    /// which stays the same for runs of numbers of lines.
+    fn fewest_lines_dimensions(
+        &self,
+        widths: &CellWidths,
+        maxima: Option<&RangeMax<'_>>,
+        sorted: &SortedWidths,
+        max_num_lines: usize,
+        maximum_width: usize,
+    ) -> Option<Layout> {
+        let along_rows = !matches!(
+            self.options.direction,
+            Direction::TopToBottom | Direction::BottomToTop
+        );
+        let num_cells = self.cells.len();
+
+        let mut best = None;
+        let mut num_lines = max_num_lines;
+        while num_lines > 0 {
+            if along_rows {
+                // The fewest lines with as many columns as this.
+                num_lines = div_ceil(num_cells, div_ceil(num_cells, num_lines));
+            }
+            match self.fitting_dimensions(widths, maxima, sorted, num_lines, maximum_width) {
+                Some(layout) => best = Some(layout),
+                None => break,
+            }
+            num_lines -= 1;
+        }
+        best
+    }
+
+    /// Looks for a narrower layout than the given one with the same number
+    /// of lines but more columns, which only exists when the cells go along
+    /// the rows.
+    fn narrowest_with_lines(
+        &self,
+        widths: &CellWidths,
+        layout: Layout,
+        maximum_width: usize,
+    ) -> Layout {
+        if matches!(
+            self.options.direction,
+            Direction::TopToBottom | Direction::BottomToTop
+        ) {
+            return layout;
+        }
+
+        let filling = &self.options.filling;
+        let num_lines = layout.num_lines;
+        let mut narrowest_width = layout.total_width(filling);
+        let mut narrowest = layout;
+        let more_columns = narrowest.widths.len() + 1..=self.cells.len();
+        for num_columns in more_columns.take_while(|&c| div_ceil(self.cells.len(), c) == num_lines)
+        {
+            let candidate = self.compute_dimensions(widths, num_lines, num_columns);
+            let width = candidate.total_width(filling);
+            if width < narrowest_width && width <= maximum_width && self.keeps_together(&candidate)
+            {
+                narrowest_width = width;
+                narrowest = candidate;
+            }
+        }
+        narrowest
+    }
+
+    /// Ranks a layout that fits by the grid's strategy, where lower is
+    /// better.
+    fn strategy_key(&self, layout: &Layout) -> (usize, usize) {
+        let num_lines = layout.num_lines;
+        match self.options.strategy {
+            Strategy::MinimizeRows | Strategy::Greedy => (num_lines, 0),
+            Strategy::MinimizeWidth => (num_lines, layout.total_width(&self.options.filling)),
+            Strategy::Balanced => (num_lines.max(layout.widths.len()), 0),
+        }
+    }
+}
+
+impl<T: Cell> FromIterator<T> for Grid<T> {
+    /// Collects the cells into a grid with the default options, which can be
+    /// replaced with [`Grid::with_options`].
+    fn from_iter<I: IntoIterator<Item = T>>(cells: I) -> Self {
+        Self::new(cells.into_iter().collect(), GridOptions::default())
+    }
+}
+
+impl<T: Cell> Extend<T> for Grid<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, cells: I) {
+        Grid::extend(self, cells);
+    }
+}
+
+/// A grid can be a cell of another grid, where it is written as a block of
+/// lines that is as wide as the inner grid.
+impl<T: Cell, M> Cell for Grid<T, M> {
+    fn width(&self) -> usize {
+        Grid::width(self)
+    }
+
+    fn height(&self) -> usize {
+        self.layout.num_lines
+    }
+
+    fn write(&self, mut f: &mut dyn fmt::Write) -> fmt::Result {
+        let renderer = RowRenderer::new(self);
+        for y in 0..self.layout.num_lines {
+            if y > 0 {
+                f.write_char('\n')?;
+            }
+            renderer.write_row(&mut f, y)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Cell, M> fmt::Display for Grid<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        self.render_rows(..).fmt(f)
+    }
+}
+
+/// A range of rows of a grid, which can be displayed on its own.
+///
+/// This is created by [`Grid::render_rows`].
+#[derive(Debug)]
+pub struct RowRange<'a, T: Cell, M = ()> {
+    grid: &'a Grid<T, M>,
+    rows: Range<usize>,
+}
+
+impl<T: Cell, M> RowRange<'_, T, M> {
+    /// The rows of the grid that will be displayed, with the range clamped
+    /// to the rows that exist.
+    pub fn rows(&self) -> Range<usize> {
+        self.rows.clone()
+    }
+}
+
+impl<T: Cell, M> fmt::Display for RowRange<'_, T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let renderer = RowRenderer::new(self.grid);
+        let options = &self.grid.options;
+        let line_ending = options.line_ending.as_str();
+        for y in self.rows.clone() {
+            renderer.write_row(f, y)?;
+            if y + 1 < self.grid.layout.num_lines || options.final_line_ending {
+                f.write_str(line_ending)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The grid split into pages with a fixed number of rows.
+///
+/// This is created by [`Grid::pages`]. It is an iterator over the pages,
+/// which keeps track of how many pages have been shown so far.
+#[derive(Debug)]
+pub struct Pages<'a, T: Cell, M = ()> {
+    grid: &'a Grid<T, M>,
+    height: usize,
+    current: usize,
+}
+
+impl<'a, T: Cell, M> Pages<'a, T, M> {
+    /// The total number of pages.
+    pub fn page_count(&self) -> usize {
+        div_ceil(self.grid.layout.num_lines, self.height)
+    }
+
+    /// The number of pages returned so far, which is also the 1-based number
+    /// of the page that was returned last.
+    pub fn current_page(&self) -> usize {
+        self.current
+    }
+
+    /// Whether there are pages left after the current one.
+    pub fn has_more(&self) -> bool {
+        self.current < self.page_count()
+    }
+
+    /// Returns the page with the given 0-based index, without moving the
+    /// current page.
+    pub fn page(&self, index: usize) -> Option<RowRange<'a, T, M>> {
+        if index >= self.page_count() {
+            return None;
+        }
+        let start = index * self.height;
+        Some(self.grid.render_rows(start..start + self.height))
+    }
+}
 
-/// Direction cells should be written in: either across or downwards.
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
-pub enum Direction {
-    /// Starts at the top left and moves rightwards, going back to the first
-    /// column for a new row, like a typewriter.
-    LeftToRight,
+impl<'a, T: Cell, M> Iterator for Pages<'a, T, M> {
+    type Item = RowRange<'a, T, M>;
 
-    /// Starts at the top left and moves downwards, going back to the first
-    /// row for a new column, like how `ls` lists files by default.
-    TopToBottom,
+    fn next(&mut self) -> Option<RowRange<'a, T, M>> {
+        let page = self.page(self.current)?;
+        self.current += 1;
+        Some(page)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.page_count() - self.current;
+        (remaining, Some(remaining))
+    }
 }
 
-/// The text to put in between each pair of columns.
+impl<T: Cell, M> ExactSizeIterator for Pages<'_, T, M> {}
+
+/// A window of rows of a grid that can be scrolled.
 ///
-/// This does not include any spaces used when aligning cells.
-#[derive(PartialEq, Eq, Debug)]
-pub enum Filling {
-    /// A number of spaces
-    Spaces(usize),
+/// This is created by [`Grid::row_window`]. The rows in the window are kept
+/// as they were rendered, so scrolling only renders the rows that come into
+/// view. This keeps scrolling through a large grid cheap, for example in a
+/// file picker that redraws on every keystroke.
+#[derive(Debug)]
+pub struct RowWindow<'a, T: Cell, M = ()> {
+    grid: &'a Grid<T, M>,
+    height: usize,
+    /// The first row in the window.
+    offset: usize,
+    /// The rendered rows in the window, without their line endings.
+    rows: VecDeque<String>,
+}
 
-    /// An arbitrary string
+impl<T: Cell, M> RowWindow<'_, T, M> {
+    /// The most rows the window shows at once.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The first row in the window.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Moves the window to start at row `offset` and returns its rendered
+    /// rows, without their line endings.
     ///
-    /// `"|"` is a common choice.
-    Text(String),
-}
+    /// The offset is clamped so that the window doesn't go past the last
+    /// row. Rows that were in the window before are reused, and only the
+    /// others are rendered. With [`OverflowPolicy::Wrap`], a row can span
+    /// several lines.
+    pub fn scroll_to(&mut self, offset: usize) -> &[String] {
+        let num_lines = self.grid.layout.num_lines;
+        let offset = offset.min(num_lines.saturating_sub(self.height));
+        let end = (offset + self.height).min(num_lines);
 
-impl Filling {
-    fn width(&self) -> usize {
-        match self {
-            Filling::Spaces(w) => *w,
-            Filling::Text(t) => ansi_width(t),
+        if offset >= self.offset + self.rows.len() || end <= self.offset {
+            self.rows.clear();
+            self.offset = offset;
+        }
+        // Drop the rows that went out of view, on either side.
+        while self.offset < offset {
+            self.rows.pop_front();
+            self.offset += 1;
+        }
+        self.rows.truncate(end - self.offset);
+
+        let renderer = RowRenderer::new(self.grid);
+        let render = |y| {
+            let mut row = String::with_capacity(self.grid.width());
+            renderer
+                .write_row(&mut row, y)
+                .expect("writing to a String cannot fail");
+            row
+        };
+        while self.offset > offset {
+            self.offset -= 1;
+            self.rows.push_front(render(self.offset));
+        }
+        while self.offset + self.rows.len() < end {
+            self.rows.push_back(render(self.offset + self.rows.len()));
         }
+        self.rows.make_contiguous()
     }
 }
 
-/// The options for a grid view that should be passed to [`Grid::new`]
-#[derive(Debug)]
-pub struct GridOptions {
-    /// The direction that the cells should be written in
-    pub direction: Direction,
+/// Places the cells of a grid that is split into groups, one group after
+/// the other.
+struct Placer<'a> {
+    direction: Direction,
+    num_columns: usize,
+    spans: &'a BTreeMap<usize, usize>,
+    /// The row and column of each cell placed so far.
+    positions: Vec<(usize, usize)>,
+    /// The first row that is still free.
+    row: usize,
+    /// The number of columns used so far.
+    columns: usize,
+}
 
-    /// The string to put in between each column of cells
-    pub filling: Filling,
+impl Placer<'_> {
+    /// The number of columns the cell with the given index takes up.
+    fn span(&self, index: usize) -> usize {
+        self.spans
+            .get(&index)
+            .map_or(1, |&span| span.min(self.num_columns))
+    }
 
-    /// The width to fill with the grid
-    pub width: usize,
-}
+    /// Places a group of cells, split into sections that each hold their
+    /// cells in the order they are placed in, starting at the first free row.
+    fn place_group(&mut self, sections: &[Vec<usize>]) {
+        if matches!(
+            self.direction,
+            Direction::TopToBottom | Direction::BottomToTop
+        ) {
+            // A cell that spans columns gets a row of its own, which splits
+            // the cells around it into bands that are placed separately.
+            let mut band: Vec<&[usize]> = Vec::new();
+            for section in sections {
+                let mut start = 0;
+                for (i, &index) in section.iter().enumerate() {
+                    if self.span(index) > 1 {
+                        band.push(&section[start..i]);
+                        self.place_band(&band);
+                        band.clear();
+                        self.positions[index] = (self.row, 0);
+                        self.columns = self.columns.max(self.span(index));
+                        self.row += 1;
+                        start = i + 1;
+                    }
+                }
+                band.push(&section[start..]);
+            }
+            self.place_band(&band);
+        } else {
+            self.columns = self.num_columns;
+            for section in sections {
+                self.place_rows(section);
+            }
+        }
+    }
 
-#[derive(PartialEq, Eq, Debug)]
-struct Dimensions {
-    /// The number of lines in the grid.
-    num_lines: usize,
+    /// Places sections of cells in columns, each section starting a new
+    /// column, using as few lines as fit in the number of columns.
+    fn place_band(&mut self, sections: &[&[usize]]) {
+        let sections: Vec<&[usize]> = sections.iter().copied().filter(|s| !s.is_empty()).collect();
+        let Some(longest) = sections.iter().map(|s| s.len()).max() else {
+            return;
+        };
+        let total: usize = sections.iter().map(|s| s.len()).sum();
+        let mut lines = div_ceil(total, self.num_columns);
+        while lines < longest
+            && sections
+                .iter()
+                .map(|s| div_ceil(s.len(), lines))
+                .sum::<usize>()
+                > self.num_columns
+        {
+            lines += 1;
+        }
 
-    /// The width of each column in the grid. The length of this vector serves
-    /// as the number of columns.
-    widths: Vec<usize>,
-}
+        let mut column = 0;
+        for section in sections {
+            for (offset, &index) in section.iter().enumerate() {
+                let r = match self.direction {
+                    Direction::BottomToTop => lines - 1 - offset % lines,
+                    _ => offset % lines,
+                };
+                self.positions[index] = (self.row + r, column + offset / lines);
+            }
+            column += div_ceil(section.len(), lines);
+        }
+        self.columns = self.columns.max(column);
+        self.row += lines;
+    }
 
-impl Dimensions {
-    fn total_width(&self, separator_width: usize) -> usize {
-        if self.widths.is_empty() {
-            0
-        } else {
-            let values = self.widths.iter().sum::<usize>();
-            let separators = separator_width * (self.widths.len() - 1);
-            values + separators
+    /// Places a section of cells in rows, starting a new row, and moving a
+    /// cell that spans columns to the next row if it doesn't fit.
+    fn place_rows(&mut self, section: &[usize]) {
+        let first_row = self.row;
+        let mut column = 0;
+        for &index in section {
+            let span = self.span(index);
+            if column + span > self.num_columns {
+                self.row += 1;
+                column = 0;
+            }
+            let mirrored = match self.direction {
+                Direction::RightToLeft => true,
+                Direction::Snake => (self.row - first_row) % 2 == 1,
+                _ => false,
+            };
+            let c = if mirrored {
+                self.num_columns - column - span
+            } else {
+                column
+            };
+            self.positions[index] = (self.row, c);
+            column += span;
+        }
+        if column > 0 {
+            self.row += 1;
         }
     }
 }
 
-/// Everything needed to format the cells with the grid options.
-#[derive(Debug)]
-pub struct Grid<T: AsRef<str>> {
-    options: GridOptions,
-    cells: Vec<T>,
-    widths: Vec<usize>,
-    widest_cell_width: usize,
-    dimensions: Dimensions,
+/// What the rows of a grid are padded and separated with.
+///
+/// This only depends on the layout, so it is worked out whenever the grid is
+/// laid out, and every time the grid is displayed after that reuses it.
+#[derive(Debug, Default)]
+struct RenderPlan {
+    /// The separator after each column but the last.
+    separators: Vec<String>,
+    /// A run of spaces as long as the longest padding, which is sliced into
+    /// instead of creating a string of spaces for each cell.
+    padding: String,
+    elastic: bool,
+    /// Whether any of the cells is taller than one line.
+    tall: bool,
+    /// Where each column starts in the line, if the padding is written with
+    /// tabs. These count from the start of the line, like the tab stops of
+    /// [`Filling::Tabs`], so the padding can be written up to them.
+    column_starts: Vec<usize>,
 }
 
-impl<T: AsRef<str>> Grid<T> {
-    /// Creates a new grid view with the given cells and options
-    pub fn new(cells: Vec<T>, options: GridOptions) -> Self {
-        let widths: Vec<usize> = cells.iter().map(|c| ansi_width(c.as_ref())).collect();
-        let widest_cell_width = widths.iter().copied().max().unwrap_or(0);
-        let width = options.width;
+impl RenderPlan {
+    fn new<T: Cell, M>(grid: &Grid<T, M>) -> Self {
+        let filling = &grid.options.filling;
+        let gaps = grid.layout.widths.len().saturating_sub(1);
+        let separators = match filling {
+            Filling::PerColumn(_) | Filling::Dynamic(_) => (0..gaps)
+                .map(|gap| filling.separator(gap).into_owned())
+                .collect(),
+            _ => vec![filling.separator(0).into_owned(); gaps],
+        };
 
-        let mut grid = Self {
-            options,
-            cells,
-            widths,
-            widest_cell_width,
-            dimensions: Dimensions {
-                num_lines: 0,
-                widths: Vec::new(),
-            },
+        // We overestimate how many spaces we need, but this is done once
+        // per layout, so it's not super important to get exactly right.
+        let padding_width = if grid.spans.is_empty() {
+            grid.layout.widths.iter().copied().max().unwrap_or(0)
+        } else {
+            grid.layout.total_width(filling)
         };
+        let padding = " ".repeat(padding_width);
 
-        grid.dimensions = grid.width_dimensions(width).unwrap_or(Dimensions {
-            num_lines: grid.cells.len(),
-            widths: vec![widest_cell_width],
-        });
+        let mut column_starts = Vec::new();
+        if let Filling::Tabs { spaces, .. } = filling {
+            let mut start = grid.gutter_width();
+            for width in grid.layout.widths.iter() {
+                column_starts.push(start);
+                start += width + spaces;
+            }
+        }
 
-        grid
+        Self {
+            separators,
+            padding,
+            elastic: matches!(filling, Filling::ElasticTabs(_)),
+            tall: grid.cells.iter().any(|cell| cell.height() > 1),
+            column_starts,
+        }
     }
+}
 
-    /// The number of terminal columns this display takes up, based on the separator
-    /// width and the number and width of the columns.
-    pub fn width(&self) -> usize {
-        self.dimensions.total_width(self.options.filling.width())
+/// Something that a [`RowRenderer`] writes rows to.
+///
+/// The contents of the cells are written with
+/// [`RowWriter::write_cell_contents`] and everything else as text, so that
+/// writers for terminal libraries can style the cells themselves.
+trait RowWriter: fmt::Write {
+    /// Writes the contents of the cell with index `num`.
+    fn write_cell_contents(&mut self, num: usize, contents: &str) -> fmt::Result {
+        let _ = num;
+        self.write_str(contents)
     }
+}
 
-    /// The number of rows this display takes up.
-    pub fn row_count(&self) -> usize {
-        self.dimensions.num_lines
+impl RowWriter for String {}
+
+impl RowWriter for fmt::Formatter<'_> {}
+
+impl RowWriter for &mut dyn fmt::Write {}
+
+/// Writes the rows of a grid with its render plan.
+struct RowRenderer<'a, T: Cell, M> {
+    grid: &'a Grid<T, M>,
+    plan: &'a RenderPlan,
+    /// Whether the rows are written without escape sequences, as with
+    /// [`GridOptions::strip_ansi`].
+    strip_ansi: bool,
+}
+
+impl<'a, T: Cell, M> RowRenderer<'a, T, M> {
+    fn new(grid: &'a Grid<T, M>) -> Self {
+        Self {
+            grid,
+            plan: &grid.plan,
+            strip_ansi: grid.options.strip_ansi,
+        }
     }
 
-    /// The width of each column
-    pub fn column_widths(&self) -> &[usize] {
-        &self.dimensions.widths
+    /// Creates a renderer that writes the rows without escape sequences, for
+    /// writers that style the contents of the cells themselves.
+    #[cfg(any(feature = "crossterm", feature = "termcolor"))]
+    fn unstyled(grid: &'a Grid<T, M>) -> Self {
+        Self {
+            strip_ansi: true,
+            ..Self::new(grid)
+        }
     }
 
-    /// Returns whether this display takes up as many columns as were allotted
-    /// to it.
+    /// The width of the given columns together with the separators between
+    /// them, which a cell that spans them takes up.
+    fn span_width(&self, columns: &Range<usize>) -> usize {
+        if columns.len() == 1 {
+            return self.grid.layout.widths[columns.start];
+        }
+        let widths: usize = self.grid.layout.widths[columns.clone()].iter().sum();
+        let filling = &self.grid.options.filling;
+        widths
+            + (columns.start..columns.end - 1)
+                .map(|gap| filling.gap_width(gap))
+                .sum::<usize>()
+    }
+
+    /// Writes the padding after something `width` columns wide in the given
+    /// columns, followed by the separator after the last of them.
     ///
-    /// It’s possible to construct tables that don’t actually use up all the
-    /// columns that they could, such as when there are more columns than
-    /// cells! In this case, a column would have a width of zero. This just
-    /// checks for that.
-    pub fn is_complete(&self) -> bool {
-        self.dimensions.widths.iter().all(|&x| x > 0)
+    /// If it is wider than the columns, which can happen with excluded
+    /// suffixes, it takes up the spaces at the start of the separator
+    /// instead. With elastic tabstops, the padding is left to whatever
+    /// displays the output, so only the separator is written.
+    fn write_gap(
+        &self,
+        f: &mut impl fmt::Write,
+        width: usize,
+        columns: Range<usize>,
+    ) -> fmt::Result {
+        if let Filling::Tabs {
+            tab_size, stops, ..
+        } = &self.grid.options.filling
+        {
+            let from = self.plan.column_starts[columns.start] + width;
+            let to = self.plan.column_starts[columns.end];
+            return write_tabbed_padding(f, from, to, stops, *tab_size);
+        }
+
+        let column_width = self.span_width(&columns);
+        let separator: &str = &self.plan.separators[columns.end - 1];
+        if self.plan.elastic {
+            return f.write_str(separator);
+        }
+
+        if width <= column_width {
+            f.write_str(&self.plan.padding[0..column_width - width])?;
+            f.write_str(separator)
+        } else {
+            let spaces = separator.len() - separator.trim_start_matches(' ').len();
+            f.write_str(&separator[(width - column_width).min(spaces)..])
+        }
     }
 
-    fn compute_dimensions(&self, num_lines: usize, num_columns: usize) -> Dimensions {
-        let mut column_widths = vec![0; num_columns];
-        for (index, cell_width) in self.widths.iter().copied().enumerate() {
-            let index = match self.options.direction {
-                Direction::LeftToRight => index % num_columns,
-                Direction::TopToBottom => index / num_lines,
-            };
-            if cell_width > column_widths[index] {
-                column_widths[index] = cell_width;
+    /// Writes the number of row `y` if the grid has line numbers, or a blank
+    /// gutter for `None`.
+    fn write_gutter(&self, f: &mut impl fmt::Write, y: Option<usize>) -> fmt::Result {
+        let width = self.grid.gutter_width();
+        match y {
+            _ if width == 0 => Ok(()),
+            Some(y) => write!(f, "{:>1$} ", y + 1, width - 1),
+            None => write!(f, "{:width$}", ""),
+        }
+    }
+
+    /// Writes row `y` of the grid, without a trailing newline.
+    fn write_row(&self, f: &mut impl RowWriter, y: usize) -> fmt::Result {
+        if let Some(header) = self.grid.layout.header_at(y) {
+            self.write_gutter(f, None)?;
+            let header = self.grid.headers.get(header);
+            let header = header.map_or("", |(_, header)| header);
+            return f.write_str(&self.strip(header));
+        }
+        match &self.grid.options.zebra {
+            Some(style) if y % 2 == 1 && !self.strip_ansi => self.write_striped_row(f, y, style),
+            _ => self.write_plain_row(f, y),
+        }
+    }
+
+    /// Writes row `y` of the grid with the given style covering each of its
+    /// lines from the left edge of the grid to the right.
+    fn write_striped_row(&self, f: &mut impl RowWriter, y: usize, style: &str) -> fmt::Result {
+        let mut row = String::new();
+        self.write_plain_row(&mut row, y)?;
+
+        let line_ending = self.grid.options.line_ending.as_str();
+        let lines: Vec<&str> = if line_ending.is_empty() {
+            vec![&row]
+        } else {
+            row.split(line_ending).collect()
+        };
+        let width = self.grid.width();
+        for (i, line) in lines.into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(line_ending)?;
             }
+            f.write_str(style)?;
+            write_restyled(f, line, style)?;
+            let padding = width.saturating_sub(ansi_width(line));
+            write!(f, "{:padding$}\x1b[0m", "")?;
         }
+        Ok(())
+    }
 
-        Dimensions {
-            num_lines,
-            widths: column_widths,
+    /// Writes row `y` of the grid as it is, without a trailing newline.
+    fn write_plain_row(&self, f: &mut impl RowWriter, y: usize) -> fmt::Result {
+        let grid = self.grid;
+        self.write_gutter(f, Some(y))?;
+        let wraps = grid.options.overflow == OverflowPolicy::Wrap && grid.clamps_cells();
+        if wraps || (self.plan.tall && self.is_tall_row(y)) {
+            return self.write_multiline_row(f, y);
+        }
+
+        // Abandon the line after the last cell in it, because that’s where
+        // the cells end.
+        let columns = grid.layout.widths.len();
+        let Some(last) = (0..columns).rposition(|x| grid.layout.index_at(y, x).is_some()) else {
+            return Ok(());
+        };
+        // The last cell of a short row is still followed by its padding and
+        // separator, as if the row went on. Only when the cells are placed
+        // upwards does the row end right after it, because the short rows
+        // are then at the top of the grid instead of at the end, and when
+        // reproducing GNU `ls`, which ends every line after its last file.
+        let end = match (grid.options.compat, grid.options.direction) {
+            (Compat::GnuLs, _) | (_, Direction::BottomToTop) => last + 1,
+            _ => columns,
+        };
+        let mut x = 0;
+        while x <= last {
+            let Some(num) = grid.layout.index_at(y, x) else {
+                // Leave a gap where a cell is missing before the end of the
+                // row, which happens when the cells are placed from the right.
+                self.write_gap(f, 0, x..x + 1)?;
+                x += 1;
+                continue;
+            };
+            let columns = x..grid.layout.span_end(y, x) + 1;
+            x = columns.end;
+
+            let last_in_row = columns.end >= end;
+            let col_width = self.span_width(&columns);
+            let gap = (!last_in_row).then_some(columns);
+            self.write_fitted_cell(f, num, col_width, gap)?;
         }
+
+        Ok(())
     }
 
-    fn theoretical_max_num_lines(&self, maximum_width: usize) -> usize {
-        // TODO: Make code readable / efficient.
-        let mut widths = self.widths.clone();
+    /// Writes the cell with index `num` into a column that is `col_width`
+    /// wide, truncating it if it doesn't fit, followed by the padding and
+    /// separator after the columns in `gap`. Returns the width of the cell
+    /// as it was written.
+    fn write_fitted_cell(
+        &self,
+        f: &mut impl RowWriter,
+        num: usize,
+        col_width: usize,
+        gap: Option<Range<usize>>,
+    ) -> Result<usize, fmt::Error> {
+        let grid = self.grid;
+        let text = grid.display_text(num);
+        let mut contents: &str = &text;
+        let suffix = grid.suffix(num);
+        let counted_suffix_width = grid.counted_suffix_width(num);
+        let indent = grid.indent(num);
+        let mut width = grid.widths.get(num) - counted_suffix_width - indent;
 
-        // Sort widths in reverse order
-        widths.sort_unstable_by(|a, b| b.cmp(a));
+        // A suffix that counts towards the width of the cell takes room
+        // away from the contents, and so does the indentation.
+        let room = col_width.saturating_sub(counted_suffix_width + indent);
+        let truncated;
+        if width > room {
+            (truncated, width) = truncate(contents, room, grid.options.text_measure());
+            contents = &truncated;
+        }
 
-        let mut col_total_width_so_far = 0;
-        for (i, &width) in widths.iter().enumerate() {
-            let adjusted_width = if i == 0 {
-                width
-            } else {
-                width + self.options.filling.width()
+        // The final column doesn’t need to have trailing spaces,
+        // as long as it’s left-aligned.
+        //
+        // We use write_str directly instead of a the write! macro to
+        // avoid some of the formatting overhead. For example, if we pad
+        // using `write!("{contents:>width}")`, the unicode width will
+        // have to be independently calculated by the macro, which is slow and
+        // redundant because we already know the width.
+        //
+        // For the padding, we instead slice into a buffer of spaces defined
+        // above, so we don't need to call `" ".repeat(n)` each loop.
+        // We also only call `write_str` when we actually need padding as
+        // another optimization.
+        let width = indent + width + ansi_width(suffix);
+        self.write_cell(f, num, contents, suffix, width, gap)?;
+        Ok(width)
+    }
+
+    /// Writes column `x` of the grid, with the cell from each row on a line
+    /// of its own and padded to the width of the column.
+    fn write_column(&self, f: &mut impl RowWriter, x: usize) -> fmt::Result {
+        let grid = self.grid;
+        let col_width = grid.layout.widths[x];
+        let line_ending = grid.options.line_ending.as_str();
+        for y in 0..grid.layout.num_lines {
+            if y > 0 {
+                f.write_str(line_ending)?;
+            }
+            // A cell that spans columns belongs to the first of them.
+            let num = (grid.layout.index_at(y, x)).filter(|&num| grid.layout.place(num) == (y, x));
+            let width = match num {
+                Some(num) => self.write_fitted_cell(f, num, col_width, None)?,
+                None => 0,
             };
-            if col_total_width_so_far + adjusted_width <= maximum_width {
-                col_total_width_so_far += adjusted_width;
-            } else {
-                return div_ceil(self.cells.len(), i);
+            if width < col_width && !self.plan.elastic {
+                f.write_str(&self.plan.padding[0..col_width - width])?;
             }
         }
+        if grid.layout.num_lines > 0 && grid.options.final_line_ending {
+            f.write_str(line_ending)?;
+        }
+        Ok(())
+    }
 
-        // If we make it to this point, we have exhausted all cells before
-        // reaching the maximum width; the theoretical max number of lines
-        // needed to display all cells is 1.
-        1
+    /// Whether row `y` of the grid holds a cell that is taller than one line.
+    fn is_tall_row(&self, y: usize) -> bool {
+        let grid = self.grid;
+        (0..grid.layout.widths.len())
+            .filter_map(|x| grid.layout.index_at(y, x))
+            .any(|num| grid.cells[num].height() > 1)
     }
 
-    fn width_dimensions(&self, maximum_width: usize) -> Option<Dimensions> {
-        if self.widest_cell_width > maximum_width {
-            // Largest cell is wider than maximum width; it is impossible to fit.
-            return None;
+    /// Writes row `y` of the grid over as many lines as it needs: the cells
+    /// that are taller than one line are split into their lines, and, when
+    /// the grid wraps cells, the ones that are wider than their column are
+    /// wrapped.
+    fn write_multiline_row(&self, f: &mut impl RowWriter, y: usize) -> fmt::Result {
+        let grid = self.grid;
+
+        // The cell in each slot of the row, with the columns it takes up.
+        let mut slots: Vec<(Option<usize>, Range<usize>)> = Vec::new();
+        let mut x = 0;
+        while x < grid.layout.widths.len() {
+            let num = grid.layout.index_at(y, x);
+            let end = num.map_or(x, |_| grid.layout.span_end(y, x)) + 1;
+            slots.push((num, x..end));
+            x = end;
         }
 
-        if self.cells.is_empty() {
-            return Some(Dimensions {
-                num_lines: 0,
-                widths: Vec::new(),
-            });
+        let texts: Vec<Option<Cow<'_, str>>> = (slots.iter())
+            .map(|(num, _)| num.map(|num| grid.display_text(num)))
+            .collect();
+        let pieces: Vec<Vec<(Cow<'_, str>, usize)>> = (slots.iter().zip(&texts))
+            .map(|((num, columns), text)| match (num, text) {
+                (Some(num), Some(text)) => {
+                    let taken = grid.counted_suffix_width(*num) + grid.indent(*num);
+                    let room = self.span_width(columns).saturating_sub(taken);
+                    if grid.cells[*num].height() > 1 {
+                        (text.split('\n'))
+                            .map(|line| (Cow::Borrowed(line), ansi_width(line)))
+                            .collect()
+                    } else if grid.options.overflow == OverflowPolicy::Wrap {
+                        wrap(text, room, grid.options.text_measure())
+                    } else {
+                        vec![truncate(text, room, grid.options.text_measure())]
+                    }
+                }
+                _ => Vec::new(),
+            })
+            .collect();
+        let height = pieces.iter().map(Vec::len).max().unwrap_or(0);
+
+        for line in 0..height {
+            if line > 0 {
+                f.write_str(grid.options.line_ending.as_str())?;
+                self.write_gutter(f, None)?;
+            }
+
+            // Stop after the last column that has something on this line.
+            let Some(last) = pieces.iter().rposition(|p| p.len() > line) else {
+                continue;
+            };
+            for (i, column) in pieces[..=last].iter().enumerate() {
+                let (num, columns) = &slots[i];
+                match (*num, column.get(line)) {
+                    (Some(num), Some((piece, width))) => {
+                        // The suffix goes after the last piece of the cell.
+                        let suffix = if line + 1 == column.len() {
+                            grid.suffix(num)
+                        } else {
+                            ""
+                        };
+                        let width = grid.indent(num) + width + ansi_width(suffix);
+                        let gap = (i < last).then(|| columns.clone());
+                        self.write_cell(f, num, piece, suffix, width, gap)?;
+                    }
+                    _ if i < last => self.write_gap(f, 0, columns.clone())?,
+                    _ => {}
+                }
+            }
         }
 
-        if self.cells.len() == 1 {
-            let cell_widths = self.widths[0];
-            return Some(Dimensions {
-                num_lines: 1,
-                widths: vec![cell_widths],
-            });
+        Ok(())
+    }
+
+    /// Writes the indentation and contents of the cell with index `num` and
+    /// its suffix, which are `width` columns wide together, followed by the
+    /// padding and separator after the columns in `gap`, or nothing if it is
+    /// the last cell in its row.
+    ///
+    /// A selected cell is highlighted, including its padding but not its
+    /// indentation.
+    fn write_cell(
+        &self,
+        f: &mut impl RowWriter,
+        num: usize,
+        contents: &str,
+        suffix: &str,
+        width: usize,
+        gap: Option<Range<usize>>,
+    ) -> fmt::Result {
+        let indent = self.grid.indent(num);
+        if indent > 0 {
+            write!(f, "{:indent$}", "")?;
+        }
+        if !self.grid.selected.contains(&num) || self.strip_ansi {
+            self.write_contents(f, num, contents)?;
+            f.write_str(&self.strip(suffix))?;
+            return match gap {
+                Some(columns) => self.write_gap(f, width, columns),
+                None => Ok(()),
+            };
         }
 
-        let theoretical_max_num_lines = self.theoretical_max_num_lines(maximum_width);
-        if theoretical_max_num_lines == 1 {
-            // This if—statement is necessary for the function to work correctly
-            // for small inputs.
-            return Some(Dimensions {
-                num_lines: 1,
-                widths: self.widths.clone(),
-            });
+        let style = &self.grid.options.selection_style;
+        let mut text = String::new();
+        self.write_contents(&mut text, num, contents)?;
+        text.push_str(suffix);
+        f.write_str(style)?;
+        write_restyled(f, &text, style)?;
+        let Some(columns) = gap else {
+            return f.write_str("\x1b[0m");
+        };
+
+        // Highlight the padding as well, but not the separator.
+        let column_width = self.span_width(&columns);
+        if width < column_width && !self.plan.elastic {
+            f.write_str(&self.plan.padding[0..column_width - width])?;
         }
-        // Instead of numbers of columns, try to find the fewest number of *lines*
-        // that the output will fit in.
-        let mut smallest_dimensions_yet = None;
-        for num_lines in (1..=theoretical_max_num_lines).rev() {
-            // The number of columns is the number of cells divided by the number
-            // of lines, *rounded up*.
-            let num_columns = div_ceil(self.cells.len(), num_lines);
+        f.write_str("\x1b[0m")?;
+        self.write_gap(f, width.max(column_width), columns)
+    }
 
-            // Early abort: if there are so many columns that the width of the
-            // *column separators* is bigger than the width of the screen, then
-            // don’t even try to tabulate it.
-            // This is actually a necessary check, because the width is stored as
-            // a usize, and making it go negative makes it huge instead, but it
-            // also serves as a speed-up.
-            let total_separator_width = (num_columns - 1) * self.options.filling.width();
-            if maximum_width < total_separator_width {
-                continue;
-            }
+    /// Writes the contents of the cell with index `num`, without its padding.
+    ///
+    /// The contents are styled with the grid's style function, if it has one,
+    /// and then lose their escape sequences if the grid is rendered without
+    /// them. With the `bidi` feature, right-to-left text is isolated so that it
+    /// stays within its column.
+    fn write_contents(&self, f: &mut impl RowWriter, num: usize, contents: &str) -> fmt::Result {
+        let contents = match &self.grid.options.style_fn {
+            Some(style_fn) => style_fn.style(num, contents),
+            None => Cow::Borrowed(contents),
+        };
+        let contents = self.strip(&contents);
+        #[cfg(feature = "bidi")]
+        let contents = bidi::isolate(&contents);
+        f.write_cell_contents(num, &contents)
+    }
 
-            // Remove the separator width from the available space.
-            let adjusted_width = maximum_width - total_separator_width;
+    /// Removes the escape sequences from `s` if the grid is rendered without
+    /// them.
+    fn strip<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        if self.strip_ansi {
+            strip_escapes(s)
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+}
 
-            let potential_dimensions = self.compute_dimensions(num_lines, num_columns);
-            if potential_dimensions.widths.iter().sum::<usize>() <= adjusted_width {
-                smallest_dimensions_yet = Some(potential_dimensions);
-            } else {
-                break;
-            }
+/// Writes the padding from column `from` of the line up to column `to`,
+/// with a tab wherever one reaches a tab stop before `to` and spaces
+/// otherwise, the same way as `indent` in GNU `ls.c`.
+///
+/// The tab stops are found with [`next_tab_stop`]. Like `indent`, a tab is
+/// only written if there is a stop past the column after `from`, so that a
+/// tab is never used for the last single column of the padding.
+fn write_tabbed_padding(
+    f: &mut impl fmt::Write,
+    mut from: usize,
+    to: usize,
+    stops: &[usize],
+    tab_size: usize,
+) -> fmt::Result {
+    while from < to {
+        let fits = next_tab_stop(from + 1, stops, tab_size).is_some_and(|stop| stop <= to);
+        if let Some(stop) = next_tab_stop(from, stops, tab_size).filter(|_| fits) {
+            f.write_char('\t')?;
+            from = stop;
+        } else {
+            f.write_char(' ')?;
+            from += 1;
         }
+    }
+    Ok(())
+}
 
-        smallest_dimensions_yet
+/// Writes `text`, starting `style` again after anything in it that resets
+/// the style.
+fn write_restyled(f: &mut impl fmt::Write, text: &str, style: &str) -> fmt::Result {
+    let mut rest = text;
+    while let Some(start) = rest.find("\x1b[") {
+        let reset = ["\x1b[0m", "\x1b[m"]
+            .into_iter()
+            .find(|reset| rest[start..].starts_with(reset));
+        let end = start + reset.map_or(2, str::len);
+        f.write_str(&rest[..end])?;
+        if reset.is_some() {
+            f.write_str(style)?;
+        }
+        rest = &rest[end..];
     }
+    f.write_str(rest)
 }
 
-impl<T: AsRef<str>> fmt::Display for Grid<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        let separator = match &self.options.filling {
-            Filling::Spaces(n) => " ".repeat(*n),
-            Filling::Text(s) => s.clone(),
-        };
+/// An iterator over the rendered lines of a grid.
+///
+/// This is created by [`Grid::lines`].
+pub struct Lines<'a, T: Cell, M = ()> {
+    renderer: RowRenderer<'a, T, M>,
+    next: usize,
+}
 
-        // Initialize a buffer of spaces. The idea here is that any cell
-        // that needs padding gets a slice of this buffer of the needed
-        // size. This avoids the need of creating a string of spaces for
-        // each cell that needs padding.
-        //
-        // We overestimate how many spaces we need, but this is not
-        // part of the loop and it's therefore not super important to
-        // get exactly right.
-        let padding = " ".repeat(self.widest_cell_width);
-
-        for y in 0..self.dimensions.num_lines {
-            for x in 0..self.dimensions.widths.len() {
-                let num = match self.options.direction {
-                    Direction::LeftToRight => y * self.dimensions.widths.len() + x,
-                    Direction::TopToBottom => y + self.dimensions.num_lines * x,
-                };
+impl<T: Cell, M> Iterator for Lines<'_, T, M> {
+    type Item = String;
 
-                // Abandon a line mid-way through if that’s where the cells end
-                if num >= self.cells.len() {
-                    continue;
-                }
+    fn next(&mut self) -> Option<String> {
+        if self.next >= self.renderer.grid.layout.num_lines {
+            return None;
+        }
 
-                let contents = &self.cells[num];
-                let width = self.widths[num];
-                let last_in_row = x == self.dimensions.widths.len() - 1;
-
-                let col_width = self.dimensions.widths[x];
-                let padding_size = col_width - width;
-
-                // The final column doesn’t need to have trailing spaces,
-                // as long as it’s left-aligned.
-                //
-                // We use write_str directly instead of a the write! macro to
-                // avoid some of the formatting overhead. For example, if we pad
-                // using `write!("{contents:>width}")`, the unicode width will
-                // have to be independently calculated by the macro, which is slow and
-                // redundant because we already know the width.
-                //
-                // For the padding, we instead slice into a buffer of spaces defined
-                // above, so we don't need to call `" ".repeat(n)` each loop.
-                // We also only call `write_str` when we actually need padding as
-                // another optimization.
-                f.write_str(contents.as_ref())?;
-                if !last_in_row {
-                    if padding_size > 0 {
-                        f.write_str(&padding[0..padding_size])?;
-                    }
-                    f.write_str(&separator)?;
-                }
-            }
-            f.write_str("\n")?;
+        let mut line = String::with_capacity(self.renderer.grid.width());
+        self.renderer
+            .write_row(&mut line, self.next)
+            .expect("writing to a String cannot fail");
+        self.next += 1;
+        Some(line)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.renderer.grid.layout.num_lines - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Cell, M> ExactSizeIterator for Lines<'_, T, M> {}
+
+/// Pushes `text` onto `out`, escaping the characters that are special in HTML.
+fn escape_html(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
         }
+    }
+}
 
-        Ok(())
+/// Pushes `field` onto `out`, quoting it if it contains the delimiter, a
+/// double quote or a line break.
+fn push_field(out: &mut String, field: &str, delimiter: char) {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&field.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(field);
     }
 }
 
@@ -332,3 +4425,19 @@ pub const fn div_ceil(lhs: usize, rhs: usize) -> usize {
         d
     }
 }
+
+/// The first of the widest of the given cells, as pairs of their index and
+/// width.
+fn widest(cells: impl Iterator<Item = (usize, usize)>) -> Option<(usize, usize)> {
+    cells.reduce(|widest, cell| if cell.1 > widest.1 { cell } else { widest })
+}
+
+/// The square root, rounded up, without floating point numbers, which need
+/// `std`.
+fn ceil_sqrt(n: usize) -> usize {
+    let mut root = 0;
+    while root * root < n {
+        root += 1;
+    }
+    root
+}
@@ -0,0 +1,41 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Sorting cells by the collation rules of the current locale, like `ls`
+//! does with `LC_COLLATE` set.
+
+use crate::ffi::strcoll;
+use crate::{Cell, Grid};
+use std::cmp::Ordering;
+use std::ffi::CString;
+
+impl<T: Cell, M> Grid<T, M> {
+    /// Sorts the cells by the collation rules of the current locale and lays
+    /// the grid out again.
+    ///
+    /// The cells are compared with [`locale_cmp`], so the order depends on
+    /// whether the program has selected a locale with `setlocale`. As with
+    /// [`Grid::sort_by`], the sort is stable and the original indices of the
+    /// cells are kept.
+    pub fn sort_by_locale(&mut self) {
+        self.sort_by(|a, b| locale_cmp(&a.text(), &b.text()));
+    }
+}
+
+/// Compares two strings with the C library's `strcoll`, so that they are
+/// ordered by the `LC_COLLATE` category of the current locale.
+///
+/// The current locale is global state of the process, and the C library
+/// starts out in the `C` locale, which compares bytes. The user's locale
+/// only applies once the program has selected it, as `ls` does by calling
+/// `setlocale(LC_ALL, "")` when it starts; this crate never calls
+/// `setlocale` itself. Strings containing a nul byte can't be passed to
+/// `strcoll` and are compared byte by byte instead. Strings that collate
+/// equally, such as ones that only differ in case in some locales, are
+/// ordered by their bytes.
+pub fn locale_cmp(a: &str, b: &str) -> Ordering {
+    let (Ok(c_a), Ok(c_b)) = (CString::new(a), CString::new(b)) else {
+        return a.cmp(b);
+    };
+    strcoll(&c_a, &c_b).then_with(|| a.cmp(b))
+}
@@ -0,0 +1,147 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Finding the widest cells of a grid without looking at all of them, to
+//! rule out layouts quickly.
+
+use crate::widths::CellWidths;
+use crate::{div_ceil, Filling};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// The number of cells whose widest cell is stored together.
+const BLOCK: usize = 32;
+
+/// The widest cell of any run of consecutive cells, which is what a column
+/// holds when the cells go down the columns.
+///
+/// The widths are split into blocks, and the widest cell is stored for every
+/// run of blocks whose length is a power of two, so that any run of cells is
+/// covered by two of those runs and the loose cells at either end.
+pub(crate) struct RangeMax<'a> {
+    widths: &'a CellWidths,
+
+    /// The widest cell in the `2^k` blocks starting at each block, for each
+    /// `k`.
+    levels: Vec<Vec<usize>>,
+}
+
+impl<'a> RangeMax<'a> {
+    pub(crate) fn new(widths: &'a CellWidths) -> Self {
+        let blocks: Vec<usize> = (0..div_ceil(widths.len(), BLOCK))
+            .map(|block| widths.range_max(block * BLOCK..((block + 1) * BLOCK).min(widths.len())))
+            .collect();
+
+        let mut levels = vec![blocks];
+        let mut run = 1;
+        while run * 2 <= levels[0].len() {
+            let previous = &levels[levels.len() - 1];
+            let level = (0..previous.len() - run)
+                .map(|i| previous[i].max(previous[i + run]))
+                .collect();
+            levels.push(level);
+            run *= 2;
+        }
+
+        Self { widths, levels }
+    }
+
+    /// The width of the widest cell in the given range, or 0 if it is empty.
+    pub(crate) fn max(&self, range: Range<usize>) -> usize {
+        let first_block = div_ceil(range.start, BLOCK);
+        let end_block = range.end / BLOCK;
+        if first_block >= end_block {
+            return self.scan(range);
+        }
+
+        let level = (end_block - first_block).ilog2() as usize;
+        let blocks = &self.levels[level];
+        let middle = blocks[first_block].max(blocks[end_block - (1 << level)]);
+        let head = self.scan(range.start..first_block * BLOCK);
+        let tail = self.scan(end_block * BLOCK..range.end);
+        middle.max(head).max(tail)
+    }
+
+    fn scan(&self, range: Range<usize>) -> usize {
+        self.widths.range_max(range)
+    }
+}
+
+/// The widths of the cells from the widest to the narrowest, with their
+/// running totals from either end, for bounding how wide a layout can be
+/// without laying it out.
+pub(crate) struct SortedWidths {
+    /// The widths, widest first.
+    descending: Vec<usize>,
+
+    /// The combined width of the `i` widest cells, for each `i`.
+    widest_totals: Vec<usize>,
+
+    /// The combined width of the `i` narrowest cells, for each `i`.
+    narrowest_totals: Vec<usize>,
+}
+
+impl SortedWidths {
+    pub(crate) fn new(widths: &CellWidths) -> Self {
+        let mut descending: Vec<usize> = widths.iter().collect();
+        descending.sort_unstable_by(|a, b| b.cmp(a));
+        let totals = |widths: &mut dyn Iterator<Item = &usize>| {
+            let mut total = 0;
+            let mut totals = vec![0];
+            totals.extend(widths.map(|width| {
+                total += width;
+                total
+            }));
+            totals
+        };
+        let widest_totals = totals(&mut descending.iter());
+        let narrowest_totals = totals(&mut descending.iter().rev());
+        Self {
+            descending,
+            widest_totals,
+            narrowest_totals,
+        }
+    }
+
+    /// The most of the widest cells that fit side by side into `width`, so
+    /// that any layout with that many columns or fewer fits.
+    pub(crate) fn most_widest(&self, filling: &Filling, width: usize) -> usize {
+        most_fitting(&self.widest_totals, filling, width)
+    }
+
+    /// The most of the narrowest cells that fit side by side into `width`,
+    /// so that no layout with more columns fits.
+    pub(crate) fn most_narrowest(&self, filling: &Filling, width: usize) -> usize {
+        most_fitting(&self.narrowest_totals, filling, width)
+    }
+
+    /// The least that the columns of any layout with the given shape add up
+    /// to, not counting the separators.
+    ///
+    /// The `k`th widest column has to be at least as wide as the cell that
+    /// comes `(k - 1) * num_lines` after the widest one, because the cells
+    /// that are wider than that can't all fit into fewer columns.
+    pub(crate) fn narrowest_total(&self, num_lines: usize, num_columns: usize) -> usize {
+        (self.descending.iter())
+            .step_by(num_lines.max(1))
+            .take(num_columns)
+            .sum()
+    }
+}
+
+/// The most cells whose running total plus the separators between them fits
+/// into `width`.
+fn most_fitting(totals: &[usize], filling: &Filling, width: usize) -> usize {
+    let fits = |count: usize| totals[count] + filling.separators_width(count) <= width;
+    let (mut low, mut high) = (0, totals.len() - 1);
+    while low < high {
+        let middle = high - (high - low) / 2;
+        if fits(middle) {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    low
+}
@@ -0,0 +1,37 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Measuring the cells of huge grids on several threads with `rayon`.
+
+use crate::{Cell, GridOptions};
+use alloc::vec::Vec;
+use rayon::prelude::*;
+
+/// The most cells that are measured on the calling thread. Handing a few
+/// thousand cells to other threads takes longer than measuring them.
+pub(crate) const MAX_SERIAL_CELLS: usize = 4096;
+
+/// The fewest cells that are worth measuring in a task of their own.
+const MIN_CELLS_PER_TASK: usize = 1024;
+
+impl GridOptions {
+    /// Measures the cells on the threads of the global `rayon` pool.
+    ///
+    /// Only the text of the cells is handed to other threads, so the cells
+    /// themselves don't need to be `Sync`. Cells that aren't strings know
+    /// their own width and are asked for it on the calling thread.
+    pub(crate) fn measure_in_parallel<T: Cell>(&self, cells: &[T]) -> Vec<usize> {
+        let texts: Vec<Result<&str, usize>> = cells
+            .iter()
+            .map(|cell| cell.as_str().ok_or_else(|| cell.width()))
+            .collect();
+        texts
+            .par_iter()
+            .with_min_len(MIN_CELLS_PER_TASK)
+            .map(|text| match *text {
+                Ok(text) => self.cell_width(text),
+                Err(width) => width,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,47 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Re-rendering a grid whenever the terminal is resized.
+
+use crate::terminal::terminal_width;
+use crate::{Cell, Grid};
+use signal_hook::consts::SIGWINCH;
+use signal_hook::iterator::Signals;
+use std::io;
+use std::ops::ControlFlow;
+
+impl<T: Cell, M> Grid<T, M> {
+    /// Renders the grid and renders it again every time the terminal is
+    /// resized, until the callback asks to stop.
+    ///
+    /// The grid is first refitted to the current width of the terminal and
+    /// passed to `callback`. After that, the calling thread blocks until a
+    /// `SIGWINCH` arrives, refits the grid to the new width and calls
+    /// `callback` with the fresh output again. If the width of the terminal
+    /// cannot be determined, the current width is kept.
+    ///
+    /// Returns an error if the signal handler could not be registered.
+    pub fn watch_resize<F>(&mut self, mut callback: F) -> io::Result<()>
+    where
+        F: FnMut(&str) -> ControlFlow<()>,
+    {
+        // Register the handler before the first render, so that a resize that
+        // happens in between is not missed.
+        let mut signals = Signals::new([SIGWINCH])?;
+
+        loop {
+            if let Some(width) = terminal_width() {
+                self.refit(width);
+            }
+            if callback(&self.to_string()).is_break() {
+                break;
+            }
+            if signals.forever().next().is_none() {
+                break;
+            }
+        }
+
+        signals.handle().close();
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Writing the cells of a grid as they arrive, for inputs that can't be
+//! collected before the grid is laid out.
+
+use crate::{
+    strip_escapes, truncate, write_tabbed_padding, Cell, Compat, Filling, GridOptions,
+    OverflowPolicy, Width,
+};
+use std::io;
+
+/// Writes cells along the rows of a grid whose columns are fixed up front,
+/// as soon as the cells arrive.
+///
+/// A [`Grid`](crate::Grid) needs all of its cells before it can choose the
+/// widths of its columns, which a pipeline like `find | grid` can't wait
+/// for. With the widths fixed ahead of time, every cell is written as soon
+/// as it is given, and every row is flushed once it is complete, so nothing
+/// but the current cell is held in memory.
+///
+/// The cells always go along the rows, as with
+/// [`Direction::LeftToRight`](crate::Direction::LeftToRight), whatever the
+/// direction in the options. A cell that is wider than its column is
+/// truncated, unless the overflow policy is
+/// [`OverflowPolicy::Collapse`], in which case it is written in full and
+/// pushes the rest of its row along. The options that need all of the cells
+/// to be known, such as line numbers and zebra stripes, are not used.
+///
+/// Call [`GridStream::finish`] after the last cell to end the last row.
+#[derive(Debug)]
+pub struct GridStream<W: io::Write> {
+    writer: W,
+    options: GridOptions,
+    widths: Vec<usize>,
+    /// The column the last cell was written to, or `None` before the first
+    /// cell.
+    column: Option<usize>,
+    /// The width of the last cell that was written.
+    last_width: usize,
+}
+
+impl<W: io::Write> GridStream<W> {
+    /// Creates a stream that writes to `writer`, with columns of the given
+    /// widths, not counting the separators.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no widths.
+    pub fn new(writer: W, options: GridOptions, widths: Vec<usize>) -> Self {
+        assert!(
+            !widths.is_empty(),
+            "a grid stream needs at least one column"
+        );
+        Self {
+            writer,
+            options,
+            widths,
+            column: None,
+            last_width: 0,
+        }
+    }
+
+    /// Creates a stream that writes to `writer`, with the given number of
+    /// columns that share the width of the options equally.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of columns is zero or the width is
+    /// [`Width::Unlimited`], which can't be shared.
+    pub fn with_columns(writer: W, options: GridOptions, num_columns: usize) -> Self {
+        assert!(
+            options.width != Width::Unlimited,
+            "an unlimited width can't be shared between columns"
+        );
+        let separators = options.filling.separators_width(num_columns);
+        let available = options.width.resolve().saturating_sub(separators);
+        let width = available / num_columns.max(1);
+        Self::new(writer, options, vec![width; num_columns])
+    }
+
+    /// The widths of the columns, not counting the separators.
+    pub fn column_widths(&self) -> &[usize] {
+        &self.widths
+    }
+
+    /// Writes a cell after the ones written so far.
+    ///
+    /// The padding and separator before the cell are written along with it,
+    /// and the writer is flushed once the row is complete.
+    pub fn write_cell(&mut self, cell: &impl Cell) -> io::Result<()> {
+        let column = match self.column {
+            None => 0,
+            Some(last) if last + 1 == self.widths.len() => {
+                let line_ending = self.options.line_ending.as_str();
+                self.writer.write_all(line_ending.as_bytes())?;
+                0
+            }
+            Some(last) => {
+                self.write_gap(last)?;
+                last + 1
+            }
+        };
+
+        let text = match cell.as_str() {
+            Some(cell) => self.options.display_text(cell),
+            None => cell.text(),
+        };
+        let mut width = self.options.measure(cell);
+        let column_width = self.widths[column];
+        let truncated;
+        let mut contents: &str = &text;
+        if width > column_width && self.options.overflow != OverflowPolicy::Collapse {
+            (truncated, width) = truncate(contents, column_width, self.options.text_measure());
+            contents = &truncated;
+        }
+        if self.options.strip_ansi {
+            self.writer.write_all(strip_escapes(contents).as_bytes())?;
+        } else {
+            self.writer.write_all(contents.as_bytes())?;
+        }
+
+        self.column = Some(column);
+        self.last_width = width;
+        if column + 1 == self.widths.len() {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Ends the last row and flushes the writer, returning it.
+    ///
+    /// Like the last line of a grid, a last row that is short of cells still
+    /// gets the padding and separator after its last cell, unless
+    /// [`GridOptions::compat`] is [`Compat::GnuLs`], and the row is ended
+    /// with the line ending if [`GridOptions::final_line_ending`] is set.
+    pub fn finish(mut self) -> io::Result<W> {
+        let short = self.column.filter(|&c| c + 1 < self.widths.len());
+        if let Some(column) = short.filter(|_| self.options.compat != Compat::GnuLs) {
+            self.write_gap(column)?;
+        }
+        if self.column.is_some() && self.options.final_line_ending {
+            let line_ending = self.options.line_ending.as_str();
+            self.writer.write_all(line_ending.as_bytes())?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Writes the padding after the last cell, which is in the given
+    /// column, followed by the separator after that column.
+    fn write_gap(&mut self, column: usize) -> io::Result<()> {
+        let filling = &self.options.filling;
+        let column_width = self.widths[column];
+        if let Filling::Tabs {
+            spaces,
+            tab_size,
+            stops,
+        } = filling
+        {
+            let start: usize = (self.widths[..column].iter()).map(|w| w + spaces).sum();
+            let end = start + column_width + spaces;
+            let mut padding = String::new();
+            write_tabbed_padding(&mut padding, start + self.last_width, end, stops, *tab_size)
+                .expect("writing to a String cannot fail");
+            return self.writer.write_all(padding.as_bytes());
+        }
+
+        let separator = filling.separator(column);
+        if matches!(filling, Filling::ElasticTabs(_)) {
+            return self.writer.write_all(separator.as_bytes());
+        }
+
+        if self.last_width <= column_width {
+            let padding = column_width - self.last_width;
+            write!(self.writer, "{:padding$}{separator}", "")
+        } else {
+            // Take the excess out of the spaces at the start of the separator.
+            let spaces = separator.len() - separator.trim_start_matches(' ').len();
+            let excess = (self.last_width - column_width).min(spaces);
+            self.writer.write_all(separator[excess..].as_bytes())
+        }
+    }
+}
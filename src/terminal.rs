@@ -0,0 +1,31 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Detection of the width of the terminal.
+
+use crate::{Cell, Direction, Filling, Grid, GridOptions, Width};
+
+/// The width of the terminal attached to standard output, if there is one.
+pub(crate) fn terminal_width() -> Option<usize> {
+    terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width.into())
+}
+
+impl<T: Cell> Grid<T> {
+    /// Creates a new grid that fills the width of the terminal.
+    ///
+    /// This uses [`Width::Auto`], so the width is taken from the terminal
+    /// attached to standard output. If there is no terminal, the `COLUMNS`
+    /// environment variable or else [`DEFAULT_WIDTH`](crate::DEFAULT_WIDTH)
+    /// is used instead.
+    pub fn new_auto(cells: Vec<T>, direction: Direction, filling: Filling) -> Self {
+        Self::new(
+            cells,
+            GridOptions {
+                direction,
+                filling,
+                width: Width::Auto,
+                ..GridOptions::default()
+            },
+        )
+    }
+}
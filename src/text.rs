@@ -0,0 +1,392 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Changing the text of cells without breaking escape sequences.
+
+use crate::WidthFn;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::Peekable;
+use unicode_width::UnicodeWidthChar;
+
+/// The character that marks where a cell was cut off.
+const ELLIPSIS: char = '…';
+
+/// Character that starts escape sequences.
+const ESC: char = '\x1b';
+
+/// The zero-width joiner, which joins emoji into a single symbol.
+const ZWJ: char = '\u{200D}';
+
+/// The OSC 8 sequence that ends a hyperlink.
+const LINK_END: &str = "\x1b]8;;\x1b\\";
+
+/// Whether `s` only holds printable ASCII characters, so that it is as
+/// wide as it is long.
+///
+/// Most file names are like this, and checking for it is much cheaper than
+/// measuring them character by character.
+pub(crate) fn is_printable_ascii(s: &str) -> bool {
+    s.bytes().all(|b| (b' '..=b'~').contains(&b))
+}
+
+/// The first tab stop after `column`: the first of `stops` past it, or
+/// else the next multiple of `tab_size`, or `None` if the tab size is zero
+/// and no stop in the list is past it.
+pub(crate) fn next_tab_stop(column: usize, stops: &[usize], tab_size: usize) -> Option<usize> {
+    match stops.iter().find(|&&stop| stop > column) {
+        Some(&stop) => Some(stop),
+        None if tab_size > 0 => Some(column + tab_size - column % tab_size),
+        None => None,
+    }
+}
+
+/// The width of a line that can contain tabs, which move on to the next tab
+/// stop, as found by [`next_tab_stop`].
+pub(crate) fn tabbed_width(line: &str, stops: &[usize], tab_size: usize) -> usize {
+    let mut width = 0;
+    for (i, part) in line.split('\t').enumerate() {
+        if i > 0 {
+            width = next_tab_stop(width, stops, tab_size).unwrap_or(width);
+        }
+        width += ansi_width::ansi_width(part);
+    }
+    width
+}
+
+/// Truncates `s` so that it is at most `max_width` columns wide, ending it
+/// with an ellipsis if anything was cut off. Returns the result along with
+/// its width.
+///
+/// Escape sequences are never cut in half and don't count towards the width.
+/// If a style is still active where the text is cut off, a reset is added at
+/// the end so that the style doesn't leak into the padding after the cell.
+/// Likewise, a hyperlink that is still open is ended after the ellipsis, so
+/// that the link stays valid and doesn't run on into the rest of the line.
+///
+/// The text is measured with `measure`, which has to be the same as the
+/// cells were measured with for the layout.
+pub(crate) fn truncate<'s>(
+    s: &'s str,
+    max_width: usize,
+    measure: Measure<'_>,
+) -> (Cow<'s, str>, usize) {
+    let full_width = measure.width(s);
+    if full_width <= max_width {
+        return (Cow::Borrowed(s), full_width);
+    }
+
+    // Leave room for the ellipsis, unless there is no room at all.
+    let ellipsis_width = if max_width > 0 { 1 } else { 0 };
+
+    let mut out = String::with_capacity(s.len());
+    let mut active = Active::default();
+    let width = take(
+        &mut tokens(s).peekable(),
+        max_width - ellipsis_width,
+        measure,
+        &mut active,
+        &mut out,
+    );
+    if ellipsis_width > 0 {
+        out.push(ELLIPSIS);
+    }
+    active.end(&mut out);
+    (Cow::Owned(out), width + ellipsis_width)
+}
+
+/// Splits `s` into pieces that are at most `max_width` columns wide, each
+/// along with its width.
+///
+/// Like [`truncate`], this never splits escape sequences. A piece that ends
+/// with a style still active is reset at its end, and the styles are applied
+/// again at the start of the next piece, so that each piece can be printed on
+/// its own line. Hyperlinks are handled the same way: a link that spans
+/// several pieces is ended and started again around every break. The text
+/// is measured with `measure`, as for [`truncate`].
+pub(crate) fn wrap<'s>(
+    s: &'s str,
+    max_width: usize,
+    measure: Measure<'_>,
+) -> Vec<(Cow<'s, str>, usize)> {
+    let full_width = measure.width(s);
+    if full_width <= max_width {
+        return vec![(Cow::Borrowed(s), full_width)];
+    }
+
+    let mut pieces: Vec<(Cow<'_, str>, usize)> = Vec::new();
+    let mut tokens = tokens(s).peekable();
+    let mut active = Active::default();
+    loop {
+        let mut piece = String::new();
+        active.restart(&mut piece);
+        let mut width = take(&mut tokens, max_width, measure, &mut active, &mut piece);
+
+        // Every piece has to contain at least one character, even if it is
+        // wider than the limit. Anything after it that doesn't make it any
+        // wider goes along with it.
+        if width == 0 {
+            if let Some(Token::Char(c, _)) = tokens.next() {
+                piece.push(c);
+                let forced = measure.width(&piece);
+                width = take(&mut tokens, forced, measure, &mut active, &mut piece);
+            }
+        }
+
+        if tokens.peek().is_none() {
+            pieces.push((Cow::Owned(piece), width));
+            return pieces;
+        }
+        active.end(&mut piece);
+        pieces.push((Cow::Owned(piece), width));
+    }
+}
+
+/// Removes all escape sequences from `s`, leaving only the text that is
+/// visible.
+pub(crate) fn strip_escapes(s: &str) -> Cow<'_, str> {
+    if !s.contains(ESC) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut out = String::with_capacity(s.len());
+    for token in tokens(s) {
+        if let Token::Char(c, _) = token {
+            out.push(c);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Replaces the tabs in `s` with spaces up to the next multiple of
+/// `tab_size` columns, counting from the start of `s`.
+///
+/// Escape sequences don't count towards the position. A `tab_size` of zero
+/// removes the tabs.
+pub(crate) fn expand_tabs(s: &str, tab_size: usize) -> Cow<'_, str> {
+    if !s.contains('\t') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut out = String::with_capacity(s.len() + tab_size);
+    let mut width = 0;
+    for token in tokens(s) {
+        match token {
+            Token::Escape(sequence) => out.push_str(sequence),
+            Token::Char('\t', _) if tab_size > 0 => {
+                let spaces = tab_size - width % tab_size;
+                out.extend(core::iter::repeat(' ').take(spaces));
+                width += spaces;
+            }
+            Token::Char('\t', _) => {}
+            Token::Char(c, char_width) => {
+                width += char_width;
+                out.push(c);
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// The width of `s`, counting emoji sequences as wide as their first emoji.
+///
+/// Anything joined on with a zero-width joiner and skin tone modifiers don't
+/// count towards the width. Escape sequences are skipped like in `ansi_width`.
+pub(crate) fn cluster_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut joined = false;
+    for token in tokens(s) {
+        match token {
+            Token::Escape(_) => {}
+            Token::Char(ZWJ, _) => joined = true,
+            Token::Char('\u{1F3FB}'..='\u{1F3FF}', _) => {}
+            Token::Char(..) if core::mem::take(&mut joined) => {}
+            Token::Char(_, char_width) => width += char_width,
+        }
+    }
+    width
+}
+
+/// A part of a text: either a whole escape sequence, or a single character
+/// along with its width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Escape(&'a str),
+    Char(char, usize),
+}
+
+/// Splits `s` into its escape sequences and the characters between them.
+///
+/// Everything in this module that measures or changes text walks it this
+/// way, so that none of it can cut an escape sequence in half or count one
+/// towards the width.
+fn tokens(s: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut rest = s;
+    core::iter::from_fn(move || {
+        let c = rest.chars().next()?;
+        let token = if c == ESC {
+            let (sequence, tail) = rest.split_at(escape_end(rest, 0));
+            rest = tail;
+            Token::Escape(sequence)
+        } else {
+            rest = &rest[c.len_utf8()..];
+            Token::Char(c, c.width().unwrap_or(0))
+        };
+        Some(token)
+    })
+}
+
+/// How to measure the width of text, which has to agree with how the cells
+/// were measured for the layout.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Measure<'a> {
+    /// The sum of the widths of the characters, like `ansi_width`.
+    Chars,
+
+    /// Like [`cluster_width`], counting emoji sequences as one emoji.
+    Clusters,
+
+    /// A custom function from the options.
+    Custom(&'a WidthFn),
+}
+
+impl Measure<'_> {
+    pub(crate) fn width(self, s: &str) -> usize {
+        match self {
+            Measure::Chars => ansi_width::ansi_width(s),
+            Measure::Clusters => cluster_width(s),
+            Measure::Custom(width_fn) => width_fn.measure(s),
+        }
+    }
+}
+
+/// Copies tokens to the end of `out` for as long as the text that `out`
+/// holds stays at most `max_width` columns wide, and returns its width.
+///
+/// This is where [`truncate`] and [`wrap`] cut the text. Escape sequences
+/// are always copied whole, and only the visible characters count towards
+/// the width. The first character that doesn't fit is left in `tokens`,
+/// along with everything after it.
+///
+/// When the width is the sum of the characters, it is counted as they are
+/// copied. Otherwise a character can change the width of the ones before
+/// it, such as a zero-width joiner between two emoji, so the whole text is
+/// measured again for every character.
+fn take<'a>(
+    tokens: &mut Peekable<impl Iterator<Item = Token<'a>>>,
+    max_width: usize,
+    measure: Measure<'_>,
+    active: &mut Active<'a>,
+    out: &mut String,
+) -> usize {
+    let mut width = measure.width(out);
+    while let Some(&token) = tokens.peek() {
+        match token {
+            Token::Escape(sequence) => {
+                active.update(sequence);
+                out.push_str(sequence);
+            }
+            Token::Char(c, char_width) => {
+                let len = out.len();
+                out.push(c);
+                let new_width = match measure {
+                    Measure::Chars => width + char_width,
+                    _ => measure.width(out),
+                };
+                if new_width > max_width {
+                    out.truncate(len);
+                    break;
+                }
+                width = new_width;
+            }
+        }
+        tokens.next();
+    }
+    width
+}
+
+/// The styles and the hyperlink that are in effect at some point of a text,
+/// which have to be ended wherever the text is cut off.
+#[derive(Debug, Default)]
+struct Active<'a> {
+    /// The style sequences since the last reset.
+    styles: String,
+
+    /// The sequence that started the open hyperlink, if there is one.
+    link: Option<&'a str>,
+}
+
+impl<'a> Active<'a> {
+    /// Takes the escape sequence that comes next in the text into account.
+    fn update(&mut self, sequence: &'a str) {
+        if sequence == "\x1b[0m" || sequence == "\x1b[m" {
+            self.styles.clear();
+        } else if sequence.starts_with("\x1b[") && sequence.ends_with('m') {
+            self.styles.push_str(sequence);
+        } else if let Some(opens) = hyperlink(sequence) {
+            self.link = opens.then_some(sequence);
+        }
+    }
+
+    /// Ends the hyperlink and resets the styles, so that neither leaks past
+    /// the end of `out`.
+    fn end(&self, out: &mut String) {
+        if self.link.is_some() {
+            out.push_str(LINK_END);
+        }
+        if !self.styles.is_empty() {
+            out.push_str("\x1b[0m");
+        }
+    }
+
+    /// Starts the styles and the hyperlink again, after they were ended.
+    fn restart(&self, out: &mut String) {
+        out.push_str(&self.styles);
+        out.push_str(self.link.unwrap_or_default());
+    }
+}
+
+/// Whether `sequence` is an OSC 8 hyperlink sequence, and if so, whether it
+/// starts a link (`true`) or ends one (`false`).
+///
+/// The sequence looks like `ESC ] 8 ; params ; URI ST`, where an empty URI
+/// ends the current link.
+fn hyperlink(sequence: &str) -> Option<bool> {
+    let rest = sequence.strip_prefix("\x1b]8;")?;
+    let (_params, uri) = rest.split_once(';')?;
+    let uri = uri
+        .strip_suffix('\x07')
+        .or_else(|| uri.strip_suffix("\x1b\\"))
+        .unwrap_or(uri);
+    Some(!uri.is_empty())
+}
+
+/// The byte offset just past the escape sequence that starts at `start`.
+///
+/// This recognises the same sequences as `ansi_width`: CSI sequences end at a
+/// character in `'\x40'..='\x7C'` and OSC sequences end at BEL or `ESC \`.
+/// For any other character after the ESC, only the ESC itself is skipped.
+fn escape_end(s: &str, start: usize) -> usize {
+    let rest = &s[start + 1..];
+    let mut chars = rest.char_indices();
+    let end = match chars.next() {
+        Some((_, '[')) => chars
+            .find(|&(_, c)| ('\x40'..='\x7C').contains(&c))
+            .map(|(i, c)| i + c.len_utf8()),
+        Some((_, ']')) => {
+            let mut last = ']';
+            chars
+                .find(|&(_, c)| {
+                    let end = c == '\x07' || (c == '\\' && last == ESC);
+                    last = c;
+                    end
+                })
+                .map(|(i, c)| i + c.len_utf8())
+        }
+        Some((_, '\\')) => Some(1),
+        _ => Some(0),
+    };
+    start + 1 + end.unwrap_or(rest.len())
+}
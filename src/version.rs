@@ -0,0 +1,135 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Sorting cells by the version numbers in them, like `ls --sort=version`.
+
+use crate::{Cell, Grid};
+use core::cmp::Ordering;
+
+impl<T: Cell, M> Grid<T, M> {
+    /// Sorts the cells by the version numbers in them and lays the grid out
+    /// again, so that `test9` comes before `test10`.
+    ///
+    /// The cells are compared with [`version_cmp`], like GNU
+    /// `ls --sort=version` does. As with [`Grid::sort_by`], the sort is
+    /// stable and the original indices of the cells are kept.
+    pub fn sort_by_version(&mut self) {
+        self.sort_by(|a, b| version_cmp(&a.text(), &b.text()));
+    }
+}
+
+/// Compares two file names by the version numbers in them, using the same
+/// rules as GNU `filevercmp`.
+///
+/// Runs of digits are compared by their numeric value, `~` sorts before
+/// everything else, even the end of the name, and file extensions such as
+/// `.tar.gz` are only compared when the rest of the names are equal. The
+/// names `.` and `..` come first, followed by other hidden files.
+pub fn version_cmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    for special in [&b""[..], b".", b".."] {
+        match (a == special, b == special) {
+            (true, _) => return Ordering::Less,
+            (_, true) => return Ordering::Greater,
+            _ => {}
+        }
+    }
+
+    match (a[0] == b'.', b[0] == b'.') {
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        _ => {}
+    }
+
+    compare_runs(&a[..prefix_len(a)], &b[..prefix_len(b)])
+        .then_with(|| compare_runs(a, b))
+        .then_with(|| a.cmp(b))
+}
+
+/// The length of the name without its extensions, which are a `.` followed
+/// by a letter or `~` and then any letters, digits and `~`, repeated up to
+/// the end of the name. For a hidden file such as `.bashrc`, the whole name
+/// can be an extension.
+fn prefix_len(name: &[u8]) -> usize {
+    let is_start = |c: u8| c.is_ascii_alphabetic() || c == b'~';
+    let is_rest = |c: u8| c.is_ascii_alphanumeric() || c == b'~';
+
+    let mut prefix_len = 0;
+    let mut i = 0;
+    loop {
+        while i + 1 < name.len() && name[i] == b'.' && is_start(name[i + 1]) {
+            i += 2;
+            while i < name.len() && is_rest(name[i]) {
+                i += 1;
+            }
+        }
+        if i >= name.len() {
+            return prefix_len;
+        }
+        i += 1;
+        prefix_len = i;
+    }
+}
+
+/// The weight of a byte outside a run of digits, like `order` in GNU
+/// `filevercmp`: the end of the name and digits sort after `~` but before
+/// everything else, and letters sort before other characters.
+fn weight(c: Option<&u8>) -> i32 {
+    match c {
+        None => 0,
+        Some(b'~') => -1,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(&c) if c.is_ascii_alphabetic() => i32::from(c),
+        Some(&c) => i32::from(c) + 256,
+    }
+}
+
+/// Compares alternating runs of non-digits and digits, like Debian's
+/// `verrevcmp`.
+fn compare_runs(a: &[u8], b: &[u8]) -> Ordering {
+    let is_digit = |c: Option<&u8>| c.is_some_and(u8::is_ascii_digit);
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+            let ordering = weight(a.get(i)).cmp(&weight(b.get(j)));
+            if ordering.is_ne() {
+                return ordering;
+            }
+            i += 1;
+            j += 1;
+        }
+
+        while a.get(i) == Some(&b'0') {
+            i += 1;
+        }
+        while b.get(j) == Some(&b'0') {
+            j += 1;
+        }
+
+        let mut first_difference = Ordering::Equal;
+        while is_digit(a.get(i)) && is_digit(b.get(j)) {
+            if first_difference.is_eq() {
+                first_difference = a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+
+        if is_digit(a.get(i)) {
+            return Ordering::Greater;
+        }
+        if is_digit(b.get(j)) {
+            return Ordering::Less;
+        }
+        if first_difference.is_ne() {
+            return first_difference;
+        }
+    }
+
+    Ordering::Equal
+}
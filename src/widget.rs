@@ -0,0 +1,127 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Showing a grid in a `ratatui` user interface.
+
+use crate::{next_tab_stop, strip_escapes, Cell, Filling, Grid, RowRenderer};
+use alloc::string::String;
+use alloc::vec;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::widgets::{StatefulWidget, Widget};
+use unicode_width::UnicodeWidthChar;
+
+/// A `ratatui` widget that shows a grid.
+///
+/// The rows are rendered as they would be written to a terminal and put
+/// into the area from its top-left corner. Lines that are wider than the
+/// area are cut off at its right edge, and the rows that don't fit below it
+/// are left out. Escape sequences in the cells are left out as well, since
+/// `ratatui` keeps the style of each position in the buffer itself: the
+/// whole area gets the style set with [`GridWidget::style`].
+///
+/// Rendered as a [`StatefulWidget`], the widget starts at the row in
+/// [`GridWidgetState::offset`], so that the grid can be scrolled.
+#[derive(Debug)]
+pub struct GridWidget<'a, T: Cell, M = ()> {
+    grid: &'a Grid<T, M>,
+    style: Style,
+}
+
+impl<'a, T: Cell, M> GridWidget<'a, T, M> {
+    /// Creates a widget that shows the given grid.
+    ///
+    /// The grid is laid out for its own width, not for the area it is
+    /// rendered into, so it should be created with the width of the area.
+    pub fn new(grid: &'a Grid<T, M>) -> Self {
+        Self {
+            grid,
+            style: Style::default(),
+        }
+    }
+
+    /// Sets the style of the whole area.
+    pub fn style(mut self, style: impl Into<Style>) -> Self {
+        self.style = style.into();
+        self
+    }
+}
+
+/// The part of a grid that a [`GridWidget`] shows.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct GridWidgetState {
+    /// The first row of the grid that is shown.
+    pub offset: usize,
+}
+
+impl<T: Cell, M> Widget for GridWidget<'_, T, M> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        StatefulWidget::render(self, area, buf, &mut GridWidgetState::default());
+    }
+}
+
+impl<T: Cell, M> StatefulWidget for GridWidget<'_, T, M> {
+    type State = GridWidgetState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = area.intersection(buf.area);
+        buf.set_style(area, self.style);
+        if area.is_empty() {
+            return;
+        }
+
+        let grid = self.grid;
+        let renderer = RowRenderer::new(grid);
+        let line_ending = grid.options.line_ending.as_str();
+        let mut lines = area.rows();
+        let mut row = String::new();
+        for y in state.offset..grid.row_count() {
+            row.clear();
+            renderer
+                .write_row(&mut row, y)
+                .expect("writing to a String cannot fail");
+            let row_lines = match line_ending {
+                "" => vec![row.as_str()],
+                _ => row.split(line_ending).collect(),
+            };
+            for line in row_lines {
+                let Some(position) = lines.next() else {
+                    return;
+                };
+                let line = expand_tab_stops(&strip_escapes(line), &grid.options.filling);
+                buf.set_stringn(
+                    position.x,
+                    position.y,
+                    line,
+                    position.width.into(),
+                    Style::default(),
+                );
+            }
+        }
+    }
+}
+
+/// Replaces the tabs in a line with spaces up to the next tab stop, since
+/// `ratatui` has no tab stops of its own.
+fn expand_tab_stops(line: &str, filling: &Filling) -> String {
+    let (stops, tab_size) = match filling {
+        Filling::Tabs {
+            stops, tab_size, ..
+        } => (&stops[..], *tab_size),
+        _ => (&[][..], 0),
+    };
+    let mut out = String::with_capacity(line.len());
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let stop = next_tab_stop(column, stops, tab_size).unwrap_or(column);
+            out.extend(core::iter::repeat(' ').take(stop - column));
+            column = stop;
+        } else {
+            out.push(c);
+            column += c.width().unwrap_or(0);
+        }
+    }
+    out
+}
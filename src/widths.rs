@@ -0,0 +1,257 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Storing the widths of cells and columns compactly.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Deref, DerefMut, Range};
+use core::slice;
+
+/// The measured width of every cell.
+///
+/// Cells are hardly ever wider than a few hundred columns, so the widths are
+/// stored in two bytes each, which keeps the widths of millions of cells
+/// small enough to stay in the cache while the layouts are searched. As soon
+/// as a single width doesn't fit into two bytes, all of them are stored as
+/// `usize` instead.
+#[derive(Clone, Debug)]
+pub(crate) enum CellWidths {
+    Compact(Vec<u16>),
+    Wide(Vec<usize>),
+}
+
+impl CellWidths {
+    pub(crate) fn new(widths: Vec<usize>) -> Self {
+        let compact: Option<Vec<u16>> = widths.iter().map(|&w| u16::try_from(w).ok()).collect();
+        match compact {
+            Some(compact) => Self::Compact(compact),
+            None => Self::Wide(widths),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::Compact(widths) => widths.len(),
+            Self::Wide(widths) => widths.len(),
+        }
+    }
+
+    /// The width of the cell with the given index.
+    pub(crate) fn get(&self, index: usize) -> usize {
+        match self {
+            Self::Compact(widths) => usize::from(widths[index]),
+            Self::Wide(widths) => widths[index],
+        }
+    }
+
+    pub(crate) fn iter(&self) -> Iter<'_> {
+        match self {
+            Self::Compact(widths) => Iter::Compact(widths.iter()),
+            Self::Wide(widths) => Iter::Wide(widths.iter()),
+        }
+    }
+
+    /// The width of the widest cell in the given range, or 0 if it is empty.
+    pub(crate) fn range_max(&self, range: Range<usize>) -> usize {
+        match self {
+            Self::Compact(widths) => widths[range].iter().copied().max().map_or(0, usize::from),
+            Self::Wide(widths) => widths[range].iter().copied().max().unwrap_or(0),
+        }
+    }
+
+    /// The width of the widest cell, or 0 if there are none.
+    pub(crate) fn max(&self) -> usize {
+        self.range_max(0..self.len())
+    }
+
+    /// The widths, none of which is wider than `max_width`.
+    pub(crate) fn clamped(&self, max_width: usize) -> Self {
+        match self {
+            Self::Compact(widths) => {
+                let max_width = u16::try_from(max_width).unwrap_or(u16::MAX);
+                Self::Compact(widths.iter().map(|&w| w.min(max_width)).collect())
+            }
+            Self::Wide(widths) => Self::new(widths.iter().map(|&w| w.min(max_width)).collect()),
+        }
+    }
+
+    pub(crate) fn set(&mut self, index: usize, width: usize) {
+        match (self, u16::try_from(width)) {
+            (Self::Compact(widths), Ok(width)) => widths[index] = width,
+            (Self::Wide(widths), _) => widths[index] = width,
+            (this, Err(_)) => {
+                this.widen();
+                this.set(index, width);
+            }
+        }
+    }
+
+    pub(crate) fn push(&mut self, width: usize) {
+        match (self, u16::try_from(width)) {
+            (Self::Compact(widths), Ok(width)) => widths.push(width),
+            (Self::Wide(widths), _) => widths.push(width),
+            (this, Err(_)) => {
+                this.widen();
+                this.push(width);
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, index: usize) -> usize {
+        match self {
+            Self::Compact(widths) => usize::from(widths.remove(index)),
+            Self::Wide(widths) => widths.remove(index),
+        }
+    }
+
+    /// Puts the widths in the order of `permutation`, which holds the old
+    /// index of each new position.
+    pub(crate) fn reorder(&mut self, permutation: &[usize]) {
+        match self {
+            Self::Compact(widths) => *widths = permutation.iter().map(|&i| widths[i]).collect(),
+            Self::Wide(widths) => *widths = permutation.iter().map(|&i| widths[i]).collect(),
+        }
+    }
+
+    /// Switches to storing the widths as `usize`, for a width that doesn't
+    /// fit into two bytes.
+    fn widen(&mut self) {
+        if let Self::Compact(widths) = self {
+            *self = Self::Wide(widths.iter().copied().map(usize::from).collect());
+        }
+    }
+}
+
+/// An iterator over the widths of the cells.
+pub(crate) enum Iter<'a> {
+    Compact(slice::Iter<'a, u16>),
+    Wide(slice::Iter<'a, usize>),
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        match self {
+            Self::Compact(iter) => iter.next().copied().map(usize::from),
+            Self::Wide(iter) => iter.next().copied(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Compact(iter) => iter.size_hint(),
+            Self::Wide(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// The most columns whose widths are kept without allocating.
+const INLINE_COLUMNS: usize = 16;
+
+/// The widths of the columns of a layout.
+///
+/// Most grids have only a handful of columns, so up to 16 widths are kept
+/// inline, and the search can try out many layouts without allocating for
+/// each of them. Wider layouts keep their widths on the heap.
+#[derive(Clone)]
+pub(crate) enum ColumnWidths {
+    Inline {
+        len: usize,
+        widths: [usize; INLINE_COLUMNS],
+    },
+    Heap(Vec<usize>),
+}
+
+impl ColumnWidths {
+    /// The widths of `len` columns that are all `width` wide.
+    pub(crate) fn filled(width: usize, len: usize) -> Self {
+        if len <= INLINE_COLUMNS {
+            Self::Inline {
+                len,
+                widths: [width; INLINE_COLUMNS],
+            }
+        } else {
+            Self::Heap(vec![width; len])
+        }
+    }
+
+    pub(crate) fn push(&mut self, width: usize) {
+        match self {
+            Self::Inline { len, widths } if *len < INLINE_COLUMNS => {
+                widths[*len] = width;
+                *len += 1;
+            }
+            Self::Inline { widths, .. } => {
+                let mut heap = widths.to_vec();
+                heap.push(width);
+                *self = Self::Heap(heap);
+            }
+            Self::Heap(widths) => widths.push(width),
+        }
+    }
+}
+
+impl Default for ColumnWidths {
+    fn default() -> Self {
+        Self::filled(0, 0)
+    }
+}
+
+impl Deref for ColumnWidths {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        match self {
+            Self::Inline { len, widths } => &widths[..*len],
+            Self::Heap(widths) => widths,
+        }
+    }
+}
+
+impl DerefMut for ColumnWidths {
+    fn deref_mut(&mut self) -> &mut [usize] {
+        match self {
+            Self::Inline { len, widths } => &mut widths[..*len],
+            Self::Heap(widths) => widths,
+        }
+    }
+}
+
+impl From<Vec<usize>> for ColumnWidths {
+    fn from(widths: Vec<usize>) -> Self {
+        if widths.len() <= INLINE_COLUMNS {
+            widths.into_iter().collect()
+        } else {
+            Self::Heap(widths)
+        }
+    }
+}
+
+impl FromIterator<usize> for ColumnWidths {
+    fn from_iter<I: IntoIterator<Item = usize>>(widths: I) -> Self {
+        let mut column_widths = Self::default();
+        for width in widths {
+            column_widths.push(width);
+        }
+        column_widths
+    }
+}
+
+impl PartialEq for ColumnWidths {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for ColumnWidths {}
+
+impl fmt::Debug for ColumnWidths {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
@@ -0,0 +1,81 @@
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Writing a grid to a `termcolor` writer, so that the cells are colored by
+//! `termcolor` instead of by escape sequences in their contents.
+
+use crate::{Cell, Grid, RowWriter};
+use core::fmt;
+use std::io;
+use termcolor::{ColorSpec, WriteColor};
+
+impl<T: Cell, M> Grid<T, M> {
+    /// Writes the grid to `writer`, coloring the contents of each cell with
+    /// the color specification that `style` returns for it.
+    ///
+    /// The style function is called with the index of the cell and the
+    /// contents that are written, which is only part of the cell if it was
+    /// truncated or wrapped. Escape sequences in the cells, the zebra stripes
+    /// and the highlighting of selected cells are left out, as with
+    /// [`GridOptions::strip_ansi`](crate::GridOptions::strip_ansi), so that
+    /// the colors only come from `termcolor`. The padding, separators and
+    /// line endings are written without a color.
+    ///
+    /// Writers that don't support colors, such as a
+    /// [`termcolor::NoColor`], get the same text as a grid with `strip_ansi`
+    /// set.
+    pub fn write_color<W: WriteColor>(
+        &self,
+        writer: &mut W,
+        style: impl FnMut(usize, &str) -> ColorSpec,
+    ) -> io::Result<()> {
+        let mut colored = Colored {
+            writer,
+            style,
+            error: None,
+        };
+        self.write_unstyled(&mut colored).map_err(|fmt::Error| {
+            (colored.error.take())
+                .unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "formatter error"))
+        })
+    }
+}
+
+/// Passes the rows that a grid writes on to a `termcolor` writer.
+struct Colored<'a, W, F> {
+    writer: &'a mut W,
+    style: F,
+    /// The error that the writer returned, which `fmt::Write` can't pass on.
+    error: Option<io::Error>,
+}
+
+impl<W: WriteColor, F> Colored<'_, W, F> {
+    /// Keeps the error of a write to return it later.
+    fn keep_error(&mut self, result: io::Result<()>) -> fmt::Result {
+        result.map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+impl<W: WriteColor, F> fmt::Write for Colored<'_, W, F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let result = self.writer.write_all(s.as_bytes());
+        self.keep_error(result)
+    }
+}
+
+impl<W: WriteColor, F: FnMut(usize, &str) -> ColorSpec> RowWriter for Colored<'_, W, F> {
+    fn write_cell_contents(&mut self, num: usize, contents: &str) -> fmt::Result {
+        let spec = (self.style)(num, contents);
+        let result = if spec.is_none() {
+            self.writer.write_all(contents.as_bytes())
+        } else {
+            (self.writer.set_color(&spec))
+                .and_then(|()| self.writer.write_all(contents.as_bytes()))
+                .and_then(|()| self.writer.reset())
+        };
+        self.keep_error(result)
+    }
+}
@@ -3,7 +3,12 @@
 
 // spell-checker:ignore underflowed
 
-use term_grid::{Direction, Filling, Grid, GridOptions};
+use std::borrow::Cow;
+use term_grid::{
+    Cell, Compat, Direction, DisplayCell, EmojiWidth, Filling, Grid, GridBuilder, GridOptions,
+    GridStream, Layout, LineEnding, OptionsError, Overflow, OverflowPolicy, SeparatorFn, Strategy,
+    StyleFn, Width, WidthFn,
+};
 
 #[test]
 fn no_items() {
@@ -12,7 +17,8 @@ fn no_items() {
         GridOptions {
             direction: Direction::TopToBottom,
             filling: Filling::Spaces(2),
-            width: 40,
+            width: Width::Columns(40),
+            ..Default::default()
         },
     );
 
@@ -26,7 +32,8 @@ fn one_item() {
         GridOptions {
             direction: Direction::TopToBottom,
             filling: Filling::Spaces(2),
-            width: 40,
+            width: Width::Columns(40),
+            ..Default::default()
         },
     );
     assert_eq!("1\n", grid.to_string());
@@ -39,7 +46,8 @@ fn one_item_exact_width() {
         GridOptions {
             direction: Direction::TopToBottom,
             filling: Filling::Spaces(2),
-            width: 10,
+            width: Width::Columns(10),
+            ..Default::default()
         },
     );
 
@@ -53,7 +61,8 @@ fn one_item_just_over() {
         GridOptions {
             direction: Direction::TopToBottom,
             filling: Filling::Spaces(2),
-            width: 10,
+            width: Width::Columns(10),
+            ..Default::default()
         },
     );
 
@@ -67,7 +76,8 @@ fn two_small_items() {
         GridOptions {
             direction: Direction::TopToBottom,
             filling: Filling::Spaces(2),
-            width: 40,
+            width: Width::Columns(40),
+            ..Default::default()
         },
     );
 
@@ -82,7 +92,8 @@ fn two_medium_size_items() {
         GridOptions {
             direction: Direction::TopToBottom,
             filling: Filling::Spaces(2),
-            width: 40,
+            width: Width::Columns(40),
+            ..Default::default()
         },
     );
 
@@ -100,7 +111,8 @@ fn two_big_items() {
         GridOptions {
             direction: Direction::TopToBottom,
             filling: Filling::Spaces(2),
-            width: 40,
+            width: Width::Columns(40),
+            ..Default::default()
         },
     );
 
@@ -117,7 +129,8 @@ fn that_example_from_earlier() {
         GridOptions {
             filling: Filling::Spaces(1),
             direction: Direction::LeftToRight,
-            width: 24,
+            width: Width::Columns(24),
+            ..Default::default()
         },
     );
 
@@ -136,7 +149,8 @@ fn number_grid_with_pipe() {
         GridOptions {
             filling: Filling::Text("|".into()),
             direction: Direction::LeftToRight,
-            width: 24,
+            width: Width::Columns(24),
+            ..Default::default()
         },
     );
 
@@ -152,7 +166,8 @@ fn huge_separator() {
         GridOptions {
             filling: Filling::Spaces(100),
             direction: Direction::LeftToRight,
-            width: 99,
+            width: Width::Columns(99),
+            ..Default::default()
         },
     );
     assert_eq!(grid.row_count(), 2);
@@ -165,7 +180,8 @@ fn huge_yet_unused_separator() {
         GridOptions {
             filling: Filling::Spaces(100),
             direction: Direction::LeftToRight,
-            width: 99,
+            width: Width::Columns(99),
+            ..Default::default()
         },
     );
 
@@ -183,12 +199,77 @@ fn emoji() {
         GridOptions {
             direction: Direction::LeftToRight,
             filling: Filling::Spaces(2),
-            width: 12,
+            width: Width::Columns(12),
+            ..Default::default()
         },
     );
     assert_eq!("🦀    hello\n👩‍🔬  hello\n", grid.to_string());
 }
 
+#[test]
+fn emoji_clusters() {
+    let cells = vec!["🦀", "hello", "👩‍🔬", "👍🏽"];
+    let options = |emoji_width| GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(80),
+        emoji_width,
+        ..Default::default()
+    };
+
+    let grid = Grid::new(cells.clone(), options(EmojiWidth::Sum));
+    assert_eq!(grid.column_widths(), &[2, 5, 4, 4]);
+
+    let grid = Grid::new(cells, options(EmojiWidth::Cluster));
+    assert_eq!(grid.column_widths(), &[2, 5, 2, 2]);
+    assert_eq!(grid.to_string(), "🦀  hello  👩‍🔬  👍🏽\n");
+}
+
+#[test]
+fn truncate_and_wrap_emoji_clusters() {
+    let options = |overflow| GridOptions {
+        direction: Direction::LeftToRight,
+        filling: Filling::Text("|".into()),
+        width: Width::Columns(5),
+        overflow,
+        emoji_width: EmojiWidth::Cluster,
+        ..Default::default()
+    };
+    let cells = || vec!["👩‍🔬👩‍🔬👩‍🔬x", "ab"];
+
+    // Each scientist is two columns wide, so two of them fit next to the
+    // ellipsis, and the cell is as wide as its column.
+    let grid = Grid::new(cells(), options(OverflowPolicy::Truncate));
+    assert_eq!(grid.column_widths(), &[5]);
+    assert_eq!(
+        grid.to_string(),
+        "👩‍🔬👩‍🔬…
+ab
+"
+    );
+
+    let grid = Grid::new(cells(), options(OverflowPolicy::Wrap));
+    assert_eq!(
+        grid.to_string(),
+        "👩‍🔬👩‍🔬
+👩‍🔬x
+ab
+"
+    );
+
+    // A custom width function is used for cutting the cells as well.
+    let icons = GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(5),
+        overflow: OverflowPolicy::Truncate,
+        width_fn: Some(WidthFn::new(|s: &str| {
+            s.chars().map(|c| if c == '\u{e5ff}' { 2 } else { 1 }).sum()
+        })),
+        ..Default::default()
+    };
+    let grid = Grid::new(vec!["\u{e5ff}\u{e5ff}\u{e5ff}", "ab"], icons);
+    assert_eq!(grid.to_string(), "\u{e5ff}\u{e5ff}…\nab\n");
+}
+
 // This test once underflowed, which should never happen. The test is just
 // checking that we do not get a panic.
 #[test]
@@ -196,46 +277,2180 @@ fn possible_underflow() {
     let cells: Vec<_> = (0..48).map(|i| 2_isize.pow(i).to_string()).collect();
 
     let grid = Grid::new(
-        cells,
+        cells,
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Text(" | ".into()),
+            width: Width::Columns(15),
+            ..Default::default()
+        },
+    );
+
+    println!("{}", grid);
+}
+
+#[test]
+fn exact_fit() {
+    let grid = Grid::new(
+        vec!["a", "b", "c", "d"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(2),
+            width: Width::Columns(4),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.row_count(), 2);
+}
+
+// This is a reproduction of https://github.com/eza-community/eza/issues/845
+#[test]
+fn eza_many_folders() {
+    let cells: Vec<_> = (100000i32..=100401).map(|i| i.to_string()).collect();
+
+    let grid = Grid::new(
+        cells,
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(2),
+            width: Width::Columns(166),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.row_count(), 20);
+}
+
+#[test]
+fn unlimited_width() {
+    let cells: Vec<_> = (0..1000).map(|i| i.to_string()).collect();
+    let grid = Grid::new(
+        cells,
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(2),
+            width: Width::Unlimited,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.row_count(), 1);
+    assert_eq!(grid.column_widths().len(), 1000);
+    assert!(grid.to_string().starts_with("0  1  2  3"));
+
+    let grid = Grid::new(
+        Vec::<String>::new(),
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(2),
+            width: Width::Unlimited,
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.row_count(), 0);
+    assert_eq!(grid.to_string(), "");
+}
+
+#[test]
+fn option_presets() {
+    let cells = vec!["one", "two", "three", "four"];
+
+    let grid = Grid::new(
+        cells.clone(),
+        GridOptions {
+            width: Width::Columns(80),
+            ..GridOptions::default()
+        },
+    );
+    assert_eq!(grid.to_string(), "one  two  three  four\n");
+
+    let grid = Grid::new(
+        cells.clone(),
+        GridOptions {
+            width: Width::Columns(80),
+            ..GridOptions::dense()
+        },
+    );
+    assert_eq!(grid.to_string(), "one two three four\n");
+
+    let grid = Grid::new(
+        cells,
+        GridOptions {
+            width: Width::Columns(12),
+            ..GridOptions::table()
+        },
+    );
+    assert_eq!(grid.to_string(), "one   | two\nthree | four\n");
+}
+
+#[test]
+fn builder() {
+    let grid = GridBuilder::new()
+        .filling(Filling::Text("|".into()))
+        .width(12)
+        .cells(["one", "two"])
+        .cells(vec!["three", "four"])
+        .build()
+        .unwrap();
+    assert_eq!(grid.to_string(), "one|three\ntwo|four\n");
+
+    let error = GridBuilder::new()
+        .width(0)
+        .cells(["a"])
+        .build()
+        .unwrap_err();
+    assert_eq!(error, OptionsError::ZeroWidth);
+}
+
+#[test]
+fn right_to_left() {
+    let grid = Grid::new(
+        vec!["one", "two", "three", "four", "five"],
+        GridOptions {
+            direction: Direction::RightToLeft,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(16),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[5, 4, 4]);
+    assert_eq!(grid.cell_position(0), Some((0, 2)));
+    assert_eq!(grid.cell_at(1, 0), None);
+    assert_eq!(grid.cell_at(1, 1), Some(&"five"));
+    assert_eq!(grid.to_string(), "three two  one\n      five four\n");
+
+    let grid = Grid::new(
+        vec!["a", "bb", "ccc"],
+        GridOptions {
+            direction: Direction::RightToLeft,
+            width: Width::Unlimited,
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.column_widths(), &[3, 2, 1]);
+    assert_eq!(grid.to_string(), "ccc  bb  a\n");
+}
+
+#[test]
+fn bottom_to_top() {
+    let grid = Grid::new(
+        vec!["one", "two", "three", "four", "five"],
+        GridOptions {
+            direction: Direction::BottomToTop,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(12),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[5, 4]);
+    assert_eq!(grid.cell_position(0), Some((2, 0)));
+    assert_eq!(grid.cell_at(0, 1), None);
+    assert_eq!(grid.to_string(), "three\ntwo   five\none   four\n");
+}
+
+#[test]
+fn snake() {
+    let grid = Grid::new(
+        vec!["1", "2", "3", "4", "5", "6", "7", "8"],
+        GridOptions {
+            direction: Direction::Snake,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(5),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.row_count(), 3);
+    assert_eq!(grid.cell_position(3), Some((1, 2)));
+    assert_eq!(grid.cell_at(1, 0), Some(&"6"));
+    assert_eq!(grid.cell_at(2, 2), None);
+    assert_eq!(grid.to_string(), "1 2 3\n6 5 4\n7 8 \n");
+}
+
+#[test]
+fn reverse_order() {
+    let cells = vec!["1", "2", "3", "4", "5"];
+    let options = |direction| GridOptions {
+        direction,
+        filling: Filling::Spaces(1),
+        width: Width::Columns(5),
+        reverse: true,
+        ..Default::default()
+    };
+
+    let grid = Grid::new(cells.clone(), options(Direction::LeftToRight));
+    assert!(grid.layout().is_reversed());
+    assert_eq!(grid.cell_position(4), Some((0, 0)));
+    assert_eq!(grid.cell_at(1, 1), Some(&"1"));
+    assert_eq!(grid.to_string(), "5 4 3\n2 1 \n");
+
+    let grid = Grid::new(cells, options(Direction::TopToBottom));
+    assert_eq!(grid.to_string(), "5 3 1\n4 2 \n");
+}
+
+#[test]
+fn transpose() {
+    let mut grid = Grid::new(
+        vec!["one", "two", "three", "four", "five"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(15),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.to_string(), "one  two  three\nfour five \n");
+
+    assert!(grid.transpose());
+    assert_eq!(grid.layout().direction(), Direction::TopToBottom);
+    assert_eq!(grid.column_widths(), &[5, 4]);
+    assert_eq!(grid.to_string(), "one   four\ntwo   five\nthree \n");
+
+    assert!(grid.transpose());
+    assert_eq!(grid.to_string(), "one  two  three\nfour five \n");
+
+    let mut grid = Grid::new(
+        vec!["1", "2", "3"],
+        GridOptions {
+            direction: Direction::Snake,
+            ..Default::default()
+        },
+    );
+    let layout = grid.layout().clone();
+    assert!(!grid.transpose());
+    assert_eq!(grid.layout(), &layout);
+}
+
+#[cfg(feature = "bidi")]
+#[test]
+fn bidi_isolation() {
+    let grid = Grid::new(
+        vec!["שלום.txt", "a.txt", "b", "مرحبا"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+
+    // The isolates don't change the layout.
+    assert_eq!(grid.column_widths(), &[8, 5, 1, 5]);
+    assert_eq!(
+        grid.to_string(),
+        "\u{2068}שלום.txt\u{2069}  a.txt  b  \u{2068}مرحبا\u{2069}\n"
+    );
+}
+
+#[test]
+fn parse_direction() {
+    assert_eq!("across".parse(), Ok(Direction::LeftToRight));
+    assert_eq!("Horizontal".parse(), Ok(Direction::LeftToRight));
+    assert_eq!("vertical".parse(), Ok(Direction::TopToBottom));
+    assert_eq!("top-to-bottom".parse(), Ok(Direction::TopToBottom));
+    assert!("diagonal".parse::<Direction>().is_err());
+
+    assert_eq!("right-to-left".parse(), Ok(Direction::RightToLeft));
+    assert_eq!("Up".parse(), Ok(Direction::BottomToTop));
+    assert_eq!("boustrophedon".parse(), Ok(Direction::Snake));
+    assert!("diagonal".parse::<Direction>().is_err());
+
+    for direction in [
+        Direction::LeftToRight,
+        Direction::TopToBottom,
+        Direction::RightToLeft,
+        Direction::BottomToTop,
+        Direction::Snake,
+    ] {
+        assert_eq!(direction.to_string().parse(), Ok(direction));
+    }
+}
+
+#[test]
+fn per_column_filling() {
+    let grid = Grid::new(
+        vec!["1", "one", "2", "two", "3", "three", "4", "four"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::PerColumn(vec![" │ ".into(), " ".into()]),
+            width: Width::Columns(16),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[1, 5, 1, 4]);
+    assert_eq!(grid.width(), 16);
+    assert_eq!(grid.to_string(), "1 │ one   2 two\n3 │ three 4 four\n");
+}
+
+#[test]
+fn dynamic_filling() {
+    let grid = Grid::new(
+        vec!["a", "b", "c", "d", "e", "f", "g", "h"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Dynamic(SeparatorFn::new(|column| {
+                if column % 2 == 0 {
+                    format!(" {column} ")
+                } else {
+                    " ".into()
+                }
+            })),
+            width: Width::Columns(11),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.width(), 11);
+    assert_eq!(grid.to_string(), "a 0 b c 2 d\ne 0 f g 2 h\n");
+}
+
+#[test]
+fn styled_filling() {
+    let grid = Grid::new(
+        vec!["one", "two", "three", "four"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Styled {
+                text: "\x1b[2m \x1b]1337;Custom\x07│ ".into(),
+                width: 3,
+            },
+            width: Width::Columns(12),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.width(), 12);
+    assert_eq!(
+        grid.to_string(),
+        "one  \x1b[2m \x1b]1337;Custom\x07│ \x1b[0mtwo\nthree\x1b[2m \x1b]1337;Custom\x07│ \x1b[0mfour\n"
+    );
+}
+
+#[test]
+fn elastic_tabs() {
+    let grid = Grid::new(
+        vec!["one", "two", "three", "four", "five"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::ElasticTabs(2),
+            width: Width::Columns(12),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[5, 4]);
+    assert_eq!(grid.width(), 11);
+    assert_eq!(grid.to_string(), "one\tfour\ntwo\tfive\nthree\t\n");
+}
+
+#[test]
+fn line_endings() {
+    let options = |line_ending| GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(10),
+        line_ending,
+        ..Default::default()
+    };
+
+    let grid = Grid::new(vec!["one", "two", "three"], options(LineEnding::CrLf));
+    assert_eq!(grid.to_string(), "one    two\r\nthree  \r\n");
+    assert_eq!(grid.lines().collect::<Vec<_>>(), ["one    two", "three  "]);
+
+    let grid = Grid::new(vec!["one", "two", "three"], options(LineEnding::Nul));
+    assert_eq!(grid.to_string(), "one    two\0three  \0");
+
+    let grid = Grid::new(vec!["one", "two"], options(LineEnding::Custom(";".into())));
+    assert_eq!(grid.to_string(), "one  two;");
+}
+
+#[test]
+fn no_final_line_ending() {
+    let grid = GridBuilder::new()
+        .direction(Direction::LeftToRight)
+        .width(10)
+        .final_line_ending(false)
+        .cells(["one", "two", "three"])
+        .build()
+        .unwrap();
+
+    assert_eq!(grid.to_string(), "one    two\nthree  ");
+    assert_eq!(grid.render_rows(..1).to_string(), "one    two\n");
+    assert_eq!(grid.render_rows(1..).to_string(), "three  ");
+}
+
+#[test]
+fn line_numbers() {
+    let cells: Vec<String> = (1..=20).map(|i| format!("c{i}")).collect();
+    let grid = Grid::new(
+        cells,
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(9),
+            line_numbers: true,
+            ..Default::default()
+        },
+    );
+
+    // Two columns would fit without the gutter, but then there would be ten
+    // rows and a gutter of three columns.
+    assert_eq!(grid.column_widths(), &[3]);
+    assert_eq!(grid.row_count(), 20);
+    assert_eq!(grid.width(), 6);
+    assert_eq!(grid.lines().next().unwrap(), " 1 c1");
+    assert_eq!(grid.render_rows(9..11).to_string(), "10 c10\n11 c11\n");
+}
+
+#[test]
+fn zebra_stripes() {
+    let grid = GridBuilder::new()
+        .direction(Direction::LeftToRight)
+        .width(11)
+        .zebra("\x1b[48;5;236m")
+        .cells(["one", "three", "\x1b[1mtwo\x1b[0m", "four", "five"])
+        .build()
+        .unwrap();
+
+    assert_eq!(grid.width(), 11);
+    assert_eq!(
+        grid.to_string(),
+        "one   three\n\x1b[48;5;236m\x1b[1mtwo\x1b[0m\x1b[48;5;236m   four \x1b[0m\nfive  \n"
+    );
+}
+
+#[test]
+fn style_cells_while_rendering() {
+    let grid = GridBuilder::new()
+        .direction(Direction::LeftToRight)
+        .width(10)
+        .style_fn(|index, contents| {
+            if index % 2 == 0 {
+                Cow::Owned(format!("\x1b[1m{contents}\x1b[0m"))
+            } else {
+                Cow::Borrowed(contents)
+            }
+        })
+        .cells(["one", "two", "three"])
+        .build()
+        .unwrap();
+
+    // The escape sequences don't affect the layout.
+    assert_eq!(grid.column_widths(), &[5, 3]);
+    assert_eq!(
+        grid.to_string(),
+        "\x1b[1mone\x1b[0m    two\n\x1b[1mthree\x1b[0m  \n"
+    );
+}
+
+#[test]
+fn selected_cells() {
+    let mut grid = GridBuilder::new()
+        .direction(Direction::LeftToRight)
+        .width(11)
+        .cells(["one", "two", "\x1b[1mthree\x1b[0m", "four"])
+        .build()
+        .unwrap();
+
+    grid.select(0);
+    grid.select(2);
+    grid.select(3);
+    grid.select(10);
+    grid.deselect(3);
+    assert!(grid.is_selected(2));
+    assert!(!grid.is_selected(3));
+    assert_eq!(
+        grid.to_string(),
+        "\x1b[7mone  \x1b[0m  two\n\x1b[7m\x1b[1mthree\x1b[0m\x1b[7m\x1b[0m  four\n"
+    );
+
+    grid.clear_selection();
+    assert_eq!(grid.to_string(), "one    two\n\x1b[1mthree\x1b[0m  four\n");
+}
+
+#[test]
+fn strip_escape_sequences() {
+    let mut grid = GridBuilder::new()
+        .direction(Direction::LeftToRight)
+        .width(11)
+        .zebra("\x1b[48;5;236m")
+        .strip_ansi(true)
+        .style_fn(|_, contents| Cow::Owned(format!("\x1b[4m{contents}\x1b[0m")))
+        .cells([
+            "one",
+            "\x1b]8;;file:///two\x1b\\two\x1b]8;;\x1b\\",
+            "\x1b[1mthree\x1b[0m",
+            "four",
+            "five",
+        ])
+        .build()
+        .unwrap();
+    grid.select(2);
+
+    assert_eq!(grid.to_string(), "one    two\nthree  four\nfive   \n");
+}
+
+#[test]
+fn placeholder_for_empty_cells() {
+    let grid = GridBuilder::new()
+        .direction(Direction::LeftToRight)
+        .width(8)
+        .placeholder("-")
+        .cells(["a", "", "", "b", "c", ""])
+        .build()
+        .unwrap();
+
+    assert_eq!(grid.to_string(), "a  -  -\nb  c  -\n");
+}
+
+#[test]
+fn suffixes() {
+    let cells = vec!["bin", "file", "link", "src"];
+    let suffixes = vec!["/".to_string(), "".into(), "@".into(), "/".into()];
+    let options = |exclude_suffixes| GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(80),
+        exclude_suffixes,
+        ..Default::default()
+    };
+
+    let grid = Grid::new_with_suffixes(cells.clone(), suffixes.clone(), options(false));
+    assert_eq!(grid.column_widths(), &[4, 4, 5, 4]);
+    assert_eq!(grid.to_string(), "bin/  file  link@  src/\n");
+
+    let grid = Grid::new_with_suffixes(cells, suffixes, options(true));
+    assert_eq!(grid.column_widths(), &[3, 4, 4, 3]);
+    assert_eq!(grid.to_string(), "bin/ file  link@ src/\n");
+}
+
+#[test]
+fn cell_metadata() {
+    #[derive(Debug, PartialEq)]
+    enum Kind {
+        Dir,
+        File,
+    }
+
+    let grid = Grid::new_with_meta(
+        vec![
+            ("src", Kind::Dir),
+            ("README.md", Kind::File),
+            ("tests", Kind::Dir),
+        ],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            width: Width::Columns(16),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.to_string(), "src        tests\nREADME.md  \n");
+    assert_eq!(grid.meta(1), Some(&Kind::File));
+    assert_eq!(grid.meta_at(0, 1), Some(&Kind::Dir));
+    assert_eq!(grid.meta_at(1, 1), None);
+    assert_eq!(grid.meta(3), None);
+
+    let grid = Grid::new(vec!["a"], GridOptions::default());
+    assert_eq!(grid.meta(0), None);
+}
+
+#[test]
+fn custom_cell_type() {
+    struct Entry {
+        name: &'static str,
+        is_dir: bool,
+    }
+
+    impl Cell for Entry {
+        fn width(&self) -> usize {
+            self.name.len() + usize::from(self.is_dir)
+        }
+
+        fn write(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
+            if self.is_dir {
+                write!(f, "\x1b[34m{}\x1b[0m/", self.name)
+            } else {
+                f.write_str(self.name)
+            }
+        }
+    }
+
+    let entry = |name, is_dir| Entry { name, is_dir };
+    let grid = Grid::new(
+        vec![
+            entry("src", true),
+            entry("Cargo.toml", false),
+            entry("a", false),
+        ],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[4, 10, 1]);
+    assert_eq!(grid.to_string(), "\x1b[34msrc\x1b[0m/  Cargo.toml  a\n");
+    assert_eq!(grid.to_delimited(','), "\x1b[34msrc\x1b[0m/,Cargo.toml,a\n");
+}
+
+#[test]
+fn display_cells() {
+    let cells: Vec<DisplayCell<u32>> = [1, 22, 333, 4444]
+        .into_iter()
+        .map(DisplayCell::new)
+        .collect();
+    let grid = Grid::new(
+        cells,
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(12),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[3, 4]);
+    assert_eq!(grid.to_string(), "1    22\n333  4444\n");
+
+    let grid = Grid::new(
+        vec![
+            DisplayCell::with_width(7, 1),
+            DisplayCell::with_width(1234, 4),
+        ],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[1, 4]);
+    assert_eq!(grid.to_string(), "7  1234\n");
+}
+
+#[test]
+fn os_strings_and_paths() {
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    let options = || GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(80),
+        ..Default::default()
+    };
+
+    let grid = Grid::from_paths(
+        [PathBuf::from("src"), PathBuf::from("Cargo.toml")],
+        options(),
+    );
+    assert_eq!(grid.to_string(), "src  Cargo.toml\n");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+
+        let names = vec![OsString::from_vec(b"caf\xe9".to_vec()), OsString::from("b")];
+        let grid = Grid::from_os_strings(names, options());
+        assert_eq!(grid.column_widths(), &[4, 1]);
+        assert_eq!(grid.to_string(), "caf\u{fffd}  b\n");
+    }
+}
+
+#[test]
+fn borrowed_slice() {
+    let names: Vec<String> = ["one", "two", "three"].map(String::from).to_vec();
+    let grid = Grid::from_slice(
+        &names,
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.to_string(), "one  two  three\n");
+    assert_eq!(names.len(), 3);
+}
+
+#[test]
+fn push_and_extend() {
+    let mut grid = Grid::new(
+        vec!["one", "two"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(16),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.to_string(), "one  two\n");
+
+    grid.push("three");
+    assert_eq!(grid.to_string(), "one  two  three\n");
+
+    grid.extend(["four", "five"]);
+    assert_eq!(grid.row_count(), 3);
+    assert_eq!(grid.to_string(), "one    two\nthree  four\nfive   \n");
+}
+
+#[test]
+fn set_and_remove() {
+    let mut grid = Grid::new(
+        vec!["one", "two", "three"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(14),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.to_string(), "one    two\nthree  \n");
+
+    assert_eq!(grid.set(2, "3"), "three");
+    assert_eq!(grid.to_string(), "one  two  3\n");
+
+    grid.select(2);
+    assert_eq!(grid.remove(0), "one");
+    assert_eq!(grid.to_string(), "two  \x1b[7m3\x1b[0m\n");
+    assert!(grid.is_selected(1));
+    assert!(!grid.is_selected(2));
+}
+
+#[test]
+fn collect_into_grid() {
+    let options = || GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(80),
+        ..Default::default()
+    };
+
+    let grid = (1..=3)
+        .map(|n| n.to_string())
+        .collect::<Grid<_>>()
+        .with_options(options());
+    assert_eq!(grid.to_string(), "1  2  3\n");
+
+    let mut grid = ["a", "b"]
+        .into_iter()
+        .collect::<GridBuilder<_>>()
+        .width(80)
+        .direction(Direction::LeftToRight)
+        .build()
+        .unwrap();
+    Extend::extend(&mut grid, ["c"]);
+    assert_eq!(grid.to_string(), "a  b  c\n");
+}
+
+#[test]
+fn sort_keeps_original_indices() {
+    let mut grid = Grid::new(
+        vec!["pear", "apple", "fig"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+    grid.select(0);
+
+    grid.sort_by_key(|name| name.len());
+    assert_eq!(grid.to_string(), "fig  \x1b[7mpear\x1b[0m  apple\n");
+    assert_eq!(grid.original_index(0), Some(2));
+    assert_eq!(grid.original_index_at(0, 2), Some(1));
+    assert_eq!(grid.original_index(3), None);
+
+    grid.sort_by(|a, b| a.cmp(b));
+    assert_eq!(grid.to_string(), "apple  fig  \x1b[7mpear\x1b[0m\n");
+    assert_eq!(grid.original_index(0), Some(1));
+    assert!(grid.is_selected(2));
+
+    grid.push("kiwi");
+    assert_eq!(grid.original_index(3), Some(3));
+}
+
+#[cfg(feature = "version-sort")]
+#[test]
+fn version_sort() {
+    use std::cmp::Ordering;
+    use term_grid::version_cmp;
+
+    assert_eq!(version_cmp("test9", "test10"), Ordering::Less);
+    assert_eq!(version_cmp("a-1.2.10", "a-1.2.9"), Ordering::Greater);
+    assert_eq!(version_cmp("file007", "file7"), Ordering::Less);
+    assert_eq!(version_cmp("1.0~rc1", "1.0"), Ordering::Less);
+    assert_eq!(version_cmp("foo.tar.gz", "foo1.tar.gz"), Ordering::Less);
+    assert_eq!(version_cmp("..", ".hidden"), Ordering::Less);
+    assert_eq!(version_cmp(".hidden", "a"), Ordering::Less);
+
+    // The same as GNU coreutils 9.1 `sort -V`: digits come after `~` and
+    // the end of the name, but before letters and punctuation.
+    assert_eq!(version_cmp("a1", "ab"), Ordering::Less);
+    assert_eq!(version_cmp("a1", "aB"), Ordering::Less);
+    assert_eq!(version_cmp("a~", "a1"), Ordering::Less);
+    assert_eq!(version_cmp("a~", "a"), Ordering::Less);
+    assert_eq!(version_cmp("a1", "a-"), Ordering::Less);
+    assert_eq!(version_cmp("a1", "a_"), Ordering::Less);
+    assert_eq!(version_cmp("ab", "a-"), Ordering::Less);
+    assert_eq!(version_cmp("2", "a"), Ordering::Less);
+    assert_eq!(version_cmp(".a", ".2"), Ordering::Less);
+    assert_eq!(version_cmp(".ab0a0", ".290Z."), Ordering::Less);
+    assert_eq!(version_cmp("x.a", "x.2"), Ordering::Less);
+
+    let mut grid = Grid::new(
+        vec!["test10", "test9", "test1"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+    grid.sort_by_version();
+    assert_eq!(grid.to_string(), "test1  test9  test10\n");
+    assert_eq!(grid.original_index(2), Some(0));
+}
+
+#[cfg(feature = "locale")]
+#[test]
+fn locale_sort() {
+    use std::cmp::Ordering;
+    use term_grid::locale_cmp;
+
+    // A program starts out in the C locale, which compares bytes, and
+    // neither the tests nor the crate ever select another one.
+    assert_eq!(locale_cmp("B", "a"), Ordering::Less);
+    assert_eq!(locale_cmp("a\0b", "a\0a"), Ordering::Greater);
+
+    let mut grid = Grid::new(
+        vec!["b", "a", "C"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+    grid.sort_by_locale();
+    assert_eq!(grid.to_string(), "C  a  b\n");
+}
+
+#[cfg(feature = "locale")]
+#[test]
+fn locale_sort_is_a_total_order() {
+    use std::cmp::Ordering;
+    use term_grid::locale_cmp;
+
+    // These hold in every locale, whichever one the process has selected.
+    let words = [
+        "a", "A", "b", "B", "é", "e", "a b", "ab", "a-b", "10", "9", "",
+    ];
+    for a in words {
+        assert_eq!(locale_cmp(a, a), Ordering::Equal);
+        for b in words {
+            assert_eq!(locale_cmp(a, b), locale_cmp(b, a).reverse(), "{a:?} {b:?}");
+            if a != b {
+                assert_ne!(locale_cmp(a, b), Ordering::Equal, "{a:?} {b:?}");
+            }
+        }
+    }
+
+    let mut sorted = words.to_vec();
+    sorted.sort_by(|a, b| locale_cmp(a, b));
+    let mut grid = Grid::new(
+        words.to_vec(),
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Unlimited,
+            ..Default::default()
+        },
+    );
+    grid.sort_by_locale();
+    let cells: Vec<&str> = (0..words.len())
+        .map(|i| *grid.cell_at(0, i).unwrap())
+        .collect();
+    assert_eq!(cells, sorted);
+}
+
+#[test]
+fn group_headers() {
+    let groups = || {
+        vec![
+            ("Documents:".to_string(), vec!["a.txt", "b.txt", "notes.md"]),
+            ("Images:".to_string(), vec!["cat.png", "dog.jpg"]),
+        ]
+    };
+
+    let grid = Grid::new_grouped(
+        groups(),
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(30),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        grid.to_string(),
+        "Documents:\na.txt    b.txt    notes.md\nImages:\ncat.png  dog.jpg  \n"
+    );
+
+    let mut grid = Grid::new_grouped(
+        groups(),
+        GridOptions {
+            direction: Direction::TopToBottom,
+            width: Width::Columns(20),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        grid.to_string(),
+        "Documents:\na.txt    notes.md\nb.txt    \nImages:\ncat.png  dog.jpg\n"
+    );
+    assert_eq!(grid.row_count(), 5);
+    assert_eq!(grid.header_at(3), Some("Images:"));
+    assert_eq!(grid.header_at(1), None);
+    assert_eq!(grid.cell_position(3), Some((4, 0)));
+    assert!(!grid.transpose());
+
+    grid.sort_by(|a, b| b.cmp(a));
+    assert_eq!(
+        grid.to_string(),
+        "Documents:\nnotes.md  a.txt\nb.txt     \nImages:\ndog.jpg   cat.png\n"
+    );
+}
+
+#[test]
+fn section_breaks() {
+    let cells = || vec!["a", "b", "c", "d", "e", "f", "g"];
+
+    let mut grid = Grid::new(
+        cells(),
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(11),
+            ..Default::default()
+        },
+    );
+    grid.set_breaks([3]);
+    assert_eq!(grid.to_string(), "a  b  c  \nd  e  f  g\n");
+
+    let mut grid = Grid::new(
+        cells(),
+        GridOptions {
+            direction: Direction::TopToBottom,
+            width: Width::Columns(8),
+            ..Default::default()
+        },
+    );
+    grid.set_breaks([3]);
+    assert_eq!(grid.to_string(), "a  d  g\nb  e  \nc  f  \n");
+
+    grid.remove(3);
+    assert_eq!(grid.to_string(), "a  e\nb  f\nc  g\n");
+}
+
+#[test]
+fn keep_together() {
+    let cells = || vec!["a", "b", "c", "d", "e", "f"];
+
+    let mut grid = Grid::new(
+        cells(),
+        GridOptions {
+            direction: Direction::TopToBottom,
+            width: Width::Columns(20),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.to_string(), "a  b  c  d  e  f\n");
+
+    grid.keep_together(2..4);
+    assert_eq!(grid.to_string(), "a  c  e\nb  d  f\n");
+
+    let mut grid = Grid::new(
+        cells(),
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(8),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.to_string(), "a  b  c\nd  e  f\n");
+
+    grid.keep_together(1..4);
+    assert_eq!(grid.to_string(), "a\nb\nc\nd\ne\nf\n");
+}
+
+#[test]
+fn spanning_cells() {
+    let mut grid = Grid::new(
+        vec!["a", "bb", "c", "dd", "Total: 4 files"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(20),
+            ..Default::default()
+        },
+    );
+    grid.set_span(4, usize::MAX);
+    assert_eq!(grid.column_widths(), &[1, 2, 1, 4]);
+    assert_eq!(grid.to_string(), "a  bb  c  dd\nTotal: 4 files\n");
+    assert_eq!(grid.rows().nth(1).unwrap().collect::<Vec<_>>(), vec![4]);
+
+    let mut grid = Grid::new(
+        vec!["a", "wide title", "b", "c", "d"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(30),
+            ..Default::default()
+        },
+    );
+    grid.set_span(1, 2);
+    assert_eq!(grid.to_string(), "a  wide title  b  c\nd  \n");
+    assert_eq!(grid.cell_position(1), Some((0, 1)));
+    assert_eq!(grid.cell_at(0, 2), Some(&"wide title"));
+}
+
+#[test]
+fn nested_grids() {
+    let options = |width| GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(width),
+        ..Default::default()
+    };
+
+    let inner = vec![
+        Grid::new(vec!["a", "b", "c", "d"], options(4)),
+        Grid::new(vec!["xyz"], options(4)),
+        Grid::new(vec!["e", "f", "g"], options(4)),
+    ];
+    assert_eq!(inner[0].height(), 2);
+
+    let grid = Grid::new(inner, options(12));
+    assert_eq!(grid.column_widths(), &[4, 3]);
+    assert_eq!(grid.to_string(), "a  b  xyz\nc  d\ne  f\ng  \n");
+}
+
+#[test]
+fn shared_column_widths() {
+    let options = || GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(20),
+        ..Default::default()
+    };
+
+    let mut grids = vec![
+        Grid::new(vec!["a", "bbbb", "c"], options()),
+        Grid::new(vec!["dd", "e", "ffffff"], options()),
+    ];
+    Grid::share_column_widths(&mut grids);
+
+    assert_eq!(grids[0].column_widths(), &[2, 4, 6]);
+    assert_eq!(grids[1].column_widths(), &[2, 4, 6]);
+    assert_eq!(grids[0].to_string(), "a   bbbb  c\n");
+    assert_eq!(grids[1].to_string(), "dd  e     ffffff\n");
+}
+
+#[test]
+fn side_by_side() {
+    let mut grid = Grid::side_by_side(
+        vec!["foo", "bar baz qux", "gone"],
+        vec!["foo", "bar"],
+        " | ",
+        GridOptions {
+            width: Width::Columns(12),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.column_widths(), &[6, 3]);
+    assert_eq!(grid.to_string(), "foo    | foo\nbar b… | bar\ngone   | \n");
+
+    grid.push("new");
+    grid.sort_by(|a, b| a.cmp(b));
+    assert_eq!(
+        grid.to_string(),
+        "bar b… | bar\nfoo    | foo\ngone   | new\n"
+    );
+}
+
+#[test]
+fn indented_cells() {
+    let mut grid = Grid::new_with_depths(
+        vec!["src", "lib.rs", "text.rs", "tests", "test.rs"],
+        vec![0, 1, 1, 0, 1],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            width: Width::Columns(20),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.column_widths(), &[9, 9]);
+    assert_eq!(
+        grid.to_string(),
+        "src        tests\n  lib.rs     test.rs\n  text.rs  \n"
+    );
+
+    grid.select(1);
+    assert_eq!(
+        grid.lines().nth(1).unwrap(),
+        "  \x1b[7mlib.rs \x1b[0m    test.rs"
+    );
+}
+
+#[test]
+fn parse_filling() {
+    assert_eq!("  ".parse(), Ok(Filling::Spaces(2)));
+    assert_eq!("".parse(), Ok(Filling::Spaces(0)));
+    assert_eq!(" | ".parse(), Ok(Filling::Text(" | ".into())));
+
+    assert_eq!(Filling::Spaces(3).to_string(), "   ");
+    assert_eq!(Filling::Text("|".into()).to_string(), "|");
+
+    let tabs = Filling::Tabs {
+        spaces: 2,
+        tab_size: 8,
+        stops: Vec::new(),
+    };
+    assert_eq!("tabs:2:8".parse(), Ok(tabs));
+    assert_eq!(
+        Filling::Tabs {
+            spaces: 1,
+            tab_size: 0,
+            stops: Vec::new(),
+        }
+        .to_string(),
+        "tabs:1:0"
+    );
+    for malformed in [
+        "tabs:",
+        "tabs:2",
+        "tabs:2:",
+        "tabs:x:8",
+        "tabs:2:8:",
+        "tabs:2:8:4,x",
+        "tabs:2:8:4:6",
+        "tabs:-1:8",
+    ] {
+        assert!(malformed.parse::<Filling>().is_err(), "{malformed:?}");
+    }
+
+    for filling in [
+        Filling::Spaces(2),
+        Filling::Text(" | ".into()),
+        Filling::Tabs {
+            spaces: 2,
+            tab_size: 8,
+            stops: Vec::new(),
+        },
+        Filling::Tabs {
+            spaces: 1,
+            tab_size: 4,
+            stops: vec![3, 10, 12],
+        },
+    ] {
+        assert_eq!(filling.to_string().parse(), Ok(filling));
+    }
+}
+
+#[test]
+fn options_validation() {
+    let options = |filling, width| GridOptions {
+        direction: Direction::LeftToRight,
+        filling,
+        width,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options(Filling::Spaces(2), Width::Columns(0)).validate(),
+        Err(OptionsError::ZeroWidth)
+    );
+    assert_eq!(
+        options(Filling::Text(" | ".into()), Width::Columns(2)).validate(),
+        Err(OptionsError::FillingTooWide {
+            filling: 3,
+            width: 2
+        })
+    );
+    assert_eq!(
+        options(Filling::Spaces(2), Width::Columns(2)).validate(),
+        Ok(())
+    );
+    assert_eq!(
+        options(Filling::Spaces(200), Width::Unlimited).validate(),
+        Ok(())
+    );
+
+    assert!(Grid::try_new(vec!["a"], options(Filling::Spaces(100), Width::Columns(99))).is_err());
+    assert!(Grid::try_new(vec!["a"], options(Filling::Spaces(1), Width::Columns(99))).is_ok());
+}
+
+#[test]
+fn overflow_report() {
+    let mut grid = Grid::new(
+        vec!["short", "a rather long cell", "tiny", "another long one"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(2),
+            width: Width::Columns(10),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[18]);
+    assert_eq!(
+        grid.overflow(),
+        Some(Overflow {
+            exceeded_by: 8,
+            offending_cells: vec![1, 3],
+        })
+    );
+
+    grid.refit(40);
+    assert_eq!(grid.overflow(), None);
+}
+
+#[test]
+fn truncate_oversized_cells() {
+    let cells = vec!["short", "a rather long cell", "tiny", "another long one"];
+    let options = |overflow| GridOptions {
+        direction: Direction::TopToBottom,
+        filling: Filling::Spaces(2),
+        width: Width::Columns(10),
+        overflow,
+        ..Default::default()
+    };
+
+    let grid = Grid::new(cells.clone(), options(OverflowPolicy::Collapse));
+    assert_eq!(grid.width(), 18);
+
+    let grid = Grid::new(cells, options(OverflowPolicy::Truncate));
+    assert_eq!(grid.overflow(), None);
+    assert_eq!(grid.width(), 10);
+    assert_eq!(grid.to_string(), "short\na rather …\ntiny\nanother l…\n");
+}
+
+#[test]
+fn truncate_styled_cells() {
+    let grid = Grid::new(
+        vec!["\x1b[31mred text\x1b[0m", "ok"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(5),
+            overflow: OverflowPolicy::Truncate,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.to_string(), "\x1b[31mred …\x1b[0m\nok\n");
+}
+
+#[test]
+fn truncate_around_escape_sequences() {
+    let options = |overflow| GridOptions {
+        direction: Direction::TopToBottom,
+        filling: Filling::Spaces(1),
+        width: Width::Columns(4),
+        overflow,
+        ..Default::default()
+    };
+    let cells = || {
+        vec![
+            "\x1b[1mab\x1b[0mcdef",
+            "ab\x1b[31mcdef\x1b[0m",
+            "\x1b[1mbo\x1b[0mld",
+        ]
+    };
+
+    // A style that was reset before the cut doesn't need another reset, but
+    // one that starts right at the cut still covers the ellipsis.
+    let grid = Grid::new(cells(), options(OverflowPolicy::Truncate));
+    assert_eq!(
+        grid.to_string(),
+        "\x1b[1mab\x1b[0mc…\nab\x1b[31mc…\x1b[0m\n\x1b[1mbo\x1b[0mld\n"
+    );
+
+    // Wrapping doesn't start a style again that was already reset.
+    let grid = Grid::new(cells(), options(OverflowPolicy::Wrap));
+    assert_eq!(
+        grid.to_string(),
+        "\x1b[1mab\x1b[0mcd\nef\nab\x1b[31mcd\x1b[0m\n\x1b[31mef\x1b[0m\n\x1b[1mbo\x1b[0mld\n"
+    );
+}
+
+#[test]
+fn wrap_oversized_cells() {
+    let grid = Grid::new(
+        vec!["a", "bb", "a rather long cell", "c"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(10),
+            overflow: OverflowPolicy::Wrap,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[10]);
+    assert_eq!(grid.overflow(), None);
+    assert_eq!(grid.row_count(), 4);
+    assert_eq!(grid.to_string(), "a\nbb\na rather l\nong cell\nc\n");
+}
+
+#[test]
+fn wrap_styled_cells() {
+    let grid = Grid::new(
+        vec!["\x1b[1mbold text\x1b[0m"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(5),
+            overflow: OverflowPolicy::Wrap,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        grid.to_string(),
+        "\x1b[1mbold \x1b[0m\n\x1b[1mtext\x1b[0m\n"
+    );
+}
+
+#[test]
+fn hyperlinks() {
+    let link = |url: &str, text: &str| format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\");
+    let cells = vec![
+        link("file:///home/user/a-very/long/path/to/somewhere", "one"),
+        link("file:///x", "two"),
+        "three".to_string(),
+        link("file:///y", "four"),
+    ];
+
+    // The length of the link target must not affect the layout.
+    let grid = Grid::new(
+        cells.clone(),
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(13),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.column_widths(), &[5, 4]);
+    assert_eq!(
+        grid.to_string(),
+        format!("{}    {}\nthree  {}\n", cells[0], cells[1], cells[3])
+    );
+
+    // Wrapping ends the link at each break and starts it again after it.
+    let grid = Grid::new(
+        vec![link("file:///z", "linked")],
+        GridOptions {
+            width: Width::Columns(4),
+            overflow: OverflowPolicy::Wrap,
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        grid.to_string(),
+        format!(
+            "{}\n{}\n",
+            link("file:///z", "link"),
+            link("file:///z", "ed")
+        )
+    );
+
+    // Truncating ends the link after the ellipsis, even though the end of
+    // the link was cut off along with the text.
+    let grid = Grid::new(
+        vec![
+            link("file:///z", "linked"),
+            link("file:///w", "\x1b[1mbolder\x1b[0m"),
+        ],
+        GridOptions {
+            width: Width::Columns(4),
+            overflow: OverflowPolicy::Truncate,
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        grid.to_string(),
+        format!(
+            "{}\n{}\x1b[0m\n",
+            link("file:///z", "lin…"),
+            link("file:///w", "\x1b[1mbol…")
+        )
+    );
+}
+
+#[test]
+fn expand_tabs_in_cells() {
+    let grid = Grid::new(
+        vec!["a\tb", "\x1b[1mab\x1b[0m\tc", "abcd\te", "x"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(20),
+            expand_tabs: Some(4),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.column_widths(), &[9, 5]);
+    assert_eq!(
+        grid.to_string(),
+        "a   b     \x1b[1mab\x1b[0m  c\nabcd    e x\n"
+    );
+
+    let options = GridOptions {
+        expand_tabs: Some(0),
+        ..Default::default()
+    };
+    assert_eq!(options.validate(), Err(OptionsError::ZeroTabSize));
+}
+
+#[test]
+fn custom_width_function() {
+    // Pretend that a private-use icon character is two columns wide.
+    let measure = |s: &str| s.chars().map(|c| if c == '\u{e5ff}' { 2 } else { 1 }).sum();
+
+    let grid = Grid::new(
+        vec!["\u{e5ff} dir", "file", "other"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(80),
+            width_fn: Some(WidthFn::new(measure)),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.column_widths(), &[6, 4, 5]);
+
+    let grid = GridBuilder::new()
+        .width(80)
+        .width_fn(|_| 3)
+        .cells(["a", "bb"])
+        .build()
+        .unwrap();
+    assert_eq!(grid.column_widths(), &[3, 3]);
+    assert_eq!(grid.to_string(), "a  bb\n");
+}
+
+#[test]
+fn explicit_widths() {
+    // The widths are trusted, even though the escape sequence is unusual.
+    let grid = Grid::new_with_widths(
+        vec!["\x1b]1337;File=icon\x07a", "bb", "ccc"],
+        vec![1, 2, 3],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.column_widths(), &[1, 2, 3]);
+    assert_eq!(grid.to_string(), "\x1b]1337;File=icon\x07a  bb  ccc\n");
+}
+
+#[test]
+#[should_panic]
+fn explicit_widths_wrong_length() {
+    Grid::new_with_widths(vec!["a", "b"], vec![1], GridOptions::default());
+}
+
+#[test]
+fn html_table() {
+    let grid = Grid::new(
+        vec!["a", "b", "c", "d", "e"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(2),
+            width: Width::Columns(7),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.row_count(), 2);
+    assert_eq!(
+        grid.to_html(),
+        "<table>\n<tr><td>a</td><td>c</td><td>e</td></tr>\n<tr><td>b</td><td>d</td></tr>\n</table>\n"
+    );
+}
+
+#[test]
+fn html_escaping() {
+    let grid = Grid::new(
+        vec!["<b>", "Tom & \"Jerry's\""],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(2),
+            width: Width::Columns(40),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        grid.to_html(),
+        "<table>\n<tr><td>&lt;b&gt;</td><td>Tom &amp; &quot;Jerry&#39;s&quot;</td></tr>\n</table>\n"
+    );
+}
+
+#[test]
+fn delimited_export() {
+    let grid = Grid::new(
+        vec!["one", "two", "three", "four", "five"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(2),
+            width: Width::Columns(18),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.row_count(), 2);
+    assert_eq!(grid.to_delimited(','), "one,two,three\nfour,five\n");
+    assert_eq!(grid.to_delimited('\t'), "one\ttwo\tthree\nfour\tfive\n");
+}
+
+#[test]
+fn delimited_quoting() {
+    let grid = Grid::new(
+        vec!["a,b", "say \"hi\"", "plain"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(2),
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.to_delimited(','), "\"a,b\",\"say \"\"hi\"\"\",plain\n");
+    assert_eq!(grid.to_delimited('\t'), "a,b\t\"say \"\"hi\"\"\"\tplain\n");
+}
+
+#[test]
+fn write_to_writer() {
+    let grid = Grid::new(
+        vec!["one", "two", "three", "four"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(10),
+            ..Default::default()
+        },
+    );
+
+    let mut out = Vec::new();
+    grid.write_to(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), grid.to_string());
+}
+
+#[test]
+fn row_widths() {
+    let options = |filling| GridOptions {
+        direction: Direction::LeftToRight,
+        filling,
+        width: Width::Columns(20),
+        ..Default::default()
+    };
+
+    let cells = vec!["one", "two", "three", "four", "five", "six", "seven"];
+    let grid = Grid::new(cells.clone(), options(Filling::Spaces(2)));
+    assert_eq!(
+        grid.to_string(),
+        "one    two   three\nfour   five  six\nseven  \n"
+    );
+    assert_eq!(grid.width(), 18);
+    assert_eq!(grid.remaining_width(), 2);
+    let widths: Vec<_> = (0..4).map(|y| grid.row_width(y)).collect();
+    assert_eq!(widths, [Some(18), Some(16), Some(7), None]);
+
+    // Tabs take up the columns up to the next tab stop.
+    let tabs = Filling::Tabs {
+        spaces: 2,
+        tab_size: 4,
+        stops: Vec::new(),
+    };
+    let grid = Grid::new(cells, options(tabs));
+    assert_eq!(
+        grid.to_string(),
+        "one    two\t three\nfour   five  six\nseven  \n"
+    );
+    assert_eq!(grid.row_width(0), Some(18));
+}
+
+#[test]
+fn widest_cells() {
+    let grid = Grid::new(
+        vec!["one", "three", "two", "seven", "four", "eleven", "six"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            width: Width::Columns(20),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        grid.to_string(),
+        "one    seven   six\nthree  four    \ntwo    eleven  \n"
+    );
+
+    assert_eq!(grid.widest_cell(), Some((5, 6)));
+    assert_eq!(grid.widest_in_column(0), Some((1, 5)));
+    assert_eq!(grid.widest_in_column(1), Some((5, 6)));
+    assert_eq!(grid.widest_in_column(2), Some((6, 3)));
+    assert_eq!(grid.widest_in_column(3), None);
+
+    let empty = Grid::new(Vec::<&str>::new(), GridOptions::default());
+    assert_eq!(empty.widest_cell(), None);
+}
+
+#[test]
+fn matrix_of_cells() {
+    let options = |direction| GridOptions {
+        direction,
+        width: Width::Columns(12),
+        ..Default::default()
+    };
+    let cells = vec!["one", "two", "three", "four", "five"];
+
+    let grid = Grid::new(cells.clone(), options(Direction::LeftToRight));
+    assert_eq!(
+        grid.as_matrix(),
+        [
+            vec![Some(&"one"), Some(&"two")],
+            vec![Some(&"three"), Some(&"four")],
+            vec![Some(&"five"), None],
+        ]
+    );
+
+    let grid = Grid::new(cells, options(Direction::TopToBottom));
+    assert_eq!(
+        grid.as_matrix(),
+        [
+            vec![Some(&"one"), Some(&"four")],
+            vec![Some(&"two"), Some(&"five")],
+            vec![Some(&"three"), None],
+        ]
+    );
+}
+
+#[test]
+fn render_single_column() {
+    let grid = Grid::new(
+        vec!["one", "two", "three", "four", "five"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(12),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.to_string(), "one    two\nthree  four\nfive   \n");
+
+    assert_eq!(grid.render_column(0), "one  \nthree\nfive \n");
+    assert_eq!(grid.render_column(1), "two \nfour\n    \n");
+    assert_eq!(grid.render_column(2), "");
+}
+
+#[test]
+fn scrolling_row_window() {
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use std::sync::Arc;
+
+    let styled = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&styled);
+    let cells: Vec<String> = (0..20).map(|n| n.to_string()).collect();
+    let grid = Grid::new(
+        cells,
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(10),
+            style_fn: Some(StyleFn::new(move |_, contents| {
+                counter.fetch_add(1, AtomicOrdering::Relaxed);
+                Cow::Borrowed(contents)
+            })),
+            ..Default::default()
+        },
+    );
+    let lines: Vec<String> = grid.lines().collect();
+    assert_eq!(lines.len(), 7);
+    styled.store(0, AtomicOrdering::Relaxed);
+
+    let mut window = grid.row_window(3);
+    assert_eq!(window.scroll_to(0), &lines[0..3]);
+    assert_eq!(styled.load(AtomicOrdering::Relaxed), 9);
+
+    // Scrolling by a row only renders the row that comes into view.
+    assert_eq!(window.scroll_to(1), &lines[1..4]);
+    assert_eq!(styled.load(AtomicOrdering::Relaxed), 12);
+    assert_eq!(window.scroll_to(0), &lines[0..3]);
+    assert_eq!(styled.load(AtomicOrdering::Relaxed), 15);
+
+    // The window stops at the last row.
+    assert_eq!(window.scroll_to(100), &lines[4..7]);
+    assert_eq!(window.offset(), 4);
+}
+
+#[test]
+fn render_reserves_output() {
+    let cells: Vec<String> = (0..1000).map(|n| (n * n).to_string()).collect();
+    let grid = Grid::new(
+        cells,
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Text(" | ".into()),
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+
+    let rendered = grid.render();
+    assert_eq!(rendered, grid.to_string());
+    assert!(rendered.capacity() >= rendered.len());
+    assert!(rendered.capacity() <= grid.row_count() * (grid.width() + 1));
+}
+
+#[test]
+fn stream_with_fixed_widths() {
+    let cells = ["one", "two", "three", "four", "five", "six", "seven"];
+    let fillings = || {
+        [
+            Filling::Spaces(2),
+            Filling::Text(" | ".into()),
+            Filling::Tabs {
+                spaces: 2,
+                tab_size: 8,
+                stops: Vec::new(),
+            },
+        ]
+    };
+    for i in 0..fillings().len() {
+        let options = || GridOptions {
+            direction: Direction::LeftToRight,
+            filling: fillings().into_iter().nth(i).unwrap(),
+            width: Width::Columns(20),
+            ..Default::default()
+        };
+        let grid = Grid::new(cells.to_vec(), options());
+        let widths = grid.column_widths().to_vec();
+
+        let mut stream = GridStream::new(Vec::new(), options(), widths);
+        for cell in cells {
+            stream.write_cell(&cell).unwrap();
+        }
+        let out = stream.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), grid.to_string());
+    }
+}
+
+#[test]
+fn stream_with_column_count() {
+    let options = |overflow| GridOptions {
+        filling: Filling::Spaces(2),
+        width: Width::Columns(20),
+        overflow,
+        final_line_ending: false,
+        ..Default::default()
+    };
+
+    let mut stream = GridStream::with_columns(Vec::new(), options(OverflowPolicy::Truncate), 3);
+    assert_eq!(stream.column_widths(), [5, 5, 5]);
+    for cell in ["a", "overlong", "b", "c"] {
+        stream.write_cell(&cell).unwrap();
+    }
+    let out = stream.finish().unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "a      over…  b\nc      ");
+
+    let mut stream = GridStream::with_columns(Vec::new(), options(OverflowPolicy::Collapse), 3);
+    for cell in ["a", "overlong", "b"] {
+        stream.write_cell(&cell).unwrap();
+    }
+    let out = stream.finish().unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "a      overlongb");
+}
+
+#[test]
+fn chunked_layout() {
+    let cells = vec![
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nineteen", "ten", "eleven",
+    ];
+    let options = || GridOptions {
+        direction: Direction::LeftToRight,
+        filling: Filling::Spaces(2),
+        width: Width::Columns(20),
+        ..Default::default()
+    };
+
+    let mut out = Vec::new();
+    Grid::write_chunked(cells.clone(), options(), 100, &mut out).unwrap();
+    let whole = Grid::new(cells.clone(), options()).to_string();
+    assert_eq!(String::from_utf8(out).unwrap(), whole);
+
+    // The later chunks keep the three columns of the first one, and their
+    // widths.
+    let mut out = Vec::new();
+    Grid::write_chunked(cells, options(), 6, &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        concat!(
+            "one   two   three\n",
+            "four  five  six\n",
+            "sev…  eig…  nine…\n",
+            "ten   ele…  \n",
+        )
+    );
+}
+
+#[test]
+fn lazy_lines() {
+    let grid = Grid::new(
+        vec!["one", "two", "three", "four", "five", "six"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(2),
+            width: Width::Columns(12),
+            ..Default::default()
+        },
+    );
+
+    let mut lines = grid.lines();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines.next().as_deref(), Some("one    four"));
+    assert_eq!(lines.len(), 2);
+
+    let joined: String = grid.lines().map(|line| line + "\n").collect();
+    assert_eq!(joined, grid.to_string());
+}
+
+#[test]
+fn render_row_range() {
+    let grid = Grid::new(
+        (1..=10).map(|i| i.to_string()).collect(),
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(4),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.to_string(), "1 6\n2 7\n3 8\n4 9\n5 10\n");
+    assert_eq!(grid.render_rows(1..3).to_string(), "2 7\n3 8\n");
+    assert_eq!(grid.render_rows(3..).to_string(), "4 9\n5 10\n");
+    assert_eq!(grid.render_rows(..=0).to_string(), "1 6\n");
+    assert_eq!(grid.render_rows(4..100).rows(), 4..5);
+    assert_eq!(grid.render_rows(7..9).to_string(), "");
+}
+
+#[test]
+fn pages() {
+    let grid = Grid::new(
+        (1..=10).map(|i| i.to_string()).collect(),
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(4),
+            ..Default::default()
+        },
+    );
+
+    let mut pages = grid.pages(2);
+    assert_eq!(pages.page_count(), 3);
+    assert_eq!(pages.current_page(), 0);
+    assert_eq!(pages.next().unwrap().to_string(), "1 6\n2 7\n");
+    assert_eq!(pages.current_page(), 1);
+    assert!(pages.has_more());
+    assert_eq!(pages.next().unwrap().to_string(), "3 8\n4 9\n");
+    assert_eq!(pages.next().unwrap().to_string(), "5 10\n");
+    assert_eq!(pages.current_page(), 3);
+    assert!(!pages.has_more());
+    assert!(pages.next().is_none());
+    assert_eq!(pages.page(1).unwrap().to_string(), "3 8\n4 9\n");
+}
+
+#[test]
+fn layout_inspection() {
+    let grid = Grid::new(
+        vec!["a", "bb", "c", "dddd", "e"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(9),
+            ..Default::default()
+        },
+    );
+
+    let layout = grid.layout();
+    assert_eq!(layout.row_count(), 2);
+    assert_eq!(layout.column_count(), 3);
+    assert_eq!(layout.column_widths(), &[2, 4, 1]);
+    assert_eq!(layout.position(3), Some((1, 1)));
+    assert_eq!(layout.position(5), None);
+    assert_eq!(layout.index_at(0, 2), Some(4));
+    assert_eq!(layout.index_at(1, 2), None);
+}
+
+#[test]
+fn cell_positions() {
+    let grid = Grid::new(
+        vec!["one", "two", "three", "four", "five"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(15),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(grid.to_string(), "one  two  three\nfour five \n");
+    assert_eq!(grid.cell_position(0), Some((0, 0)));
+    assert_eq!(grid.cell_position(2), Some((0, 2)));
+    assert_eq!(grid.cell_position(4), Some((1, 1)));
+    assert_eq!(grid.cell_position(5), None);
+}
+
+#[test]
+fn cell_lookup() {
+    for (direction, expected) in [
+        (
+            Direction::LeftToRight,
+            [Some("one"), Some("two"), Some("four"), None],
+        ),
+        (
+            Direction::TopToBottom,
+            [Some("one"), Some("three"), Some("four"), None],
+        ),
+    ] {
+        let grid = Grid::new(
+            vec!["one", "two", "three", "four"],
+            GridOptions {
+                direction,
+                filling: Filling::Spaces(1),
+                width: Width::Columns(15),
+                ..Default::default()
+            },
+        );
+
+        let found = [
+            grid.cell_at(0, 0),
+            grid.cell_at(0, 1),
+            grid.cell_at(1, 1),
+            grid.cell_at(2, 0),
+        ];
+        assert_eq!(found.map(|cell| cell.copied()), expected);
+    }
+}
+
+#[test]
+fn row_and_column_indices() {
+    let grid = Grid::new(
+        vec!["a", "b", "c", "d", "e"],
         GridOptions {
             direction: Direction::TopToBottom,
-            filling: Filling::Text(" | ".into()),
-            width: 15,
+            filling: Filling::Spaces(2),
+            width: Width::Columns(7),
+            ..Default::default()
         },
     );
 
-    println!("{}", grid);
+    let rows: Vec<Vec<usize>> = grid.rows().map(Iterator::collect).collect();
+    assert_eq!(rows, [vec![0, 2, 4], vec![1, 3]]);
+
+    let columns: Vec<Vec<usize>> = grid.columns().map(Iterator::collect).collect();
+    assert_eq!(columns, [vec![0, 1], vec![2, 3], vec![4]]);
 }
 
 #[test]
-fn exact_fit() {
-    let grid = Grid::new(
-        vec!["a", "b", "c", "d"],
+fn refit_to_new_width() {
+    let cells = vec!["one", "two", "three", "four", "five", "six"];
+    let options = |width| GridOptions {
+        direction: Direction::TopToBottom,
+        filling: Filling::Spaces(2),
+        width: Width::Columns(width),
+        ..Default::default()
+    };
+
+    let mut grid = Grid::new(cells.clone(), options(80));
+    assert_eq!(grid.row_count(), 1);
+
+    for width in [10, 20, 3, 80] {
+        grid.refit(width);
+        let fresh = Grid::new(cells.clone(), options(width));
+        assert_eq!(grid.layout(), fresh.layout());
+        assert_eq!(grid.to_string(), fresh.to_string());
+    }
+}
+
+#[test]
+fn render_plan_follows_layout() {
+    let cells = vec!["a", "bb", "ccc", "dddd", "eeeee", "ffffff", "g"];
+    let options = |direction, width| GridOptions {
+        direction,
+        filling: Filling::Tabs {
+            spaces: 2,
+            tab_size: 4,
+            stops: Vec::new(),
+        },
+        width: Width::Columns(width),
+        ..Default::default()
+    };
+
+    let mut grid = Grid::new(cells.clone(), options(Direction::TopToBottom, 80));
+    let first = grid.to_string();
+    assert_eq!(grid.to_string(), first);
+
+    grid.refit(20);
+    let fresh = Grid::new(cells.clone(), options(Direction::TopToBottom, 20));
+    assert_eq!(grid.to_string(), fresh.to_string());
+
+    assert!(grid.transpose());
+    let layout = grid.layout().clone();
+    let fresh = Grid::with_layout(cells, options(Direction::LeftToRight, 20), layout).unwrap();
+    assert_eq!(grid.to_string(), fresh.to_string());
+}
+
+#[test]
+fn render_with_cached_layout() {
+    let options = || GridOptions {
+        direction: Direction::LeftToRight,
+        filling: Filling::Spaces(2),
+        width: Width::Columns(12),
+        ..Default::default()
+    };
+    let layout: Layout = Grid::new(vec!["one", "two", "three", "four"], options())
+        .layout()
+        .clone();
+
+    let grid = Grid::with_layout(vec!["1", "2", "3", "4"], options(), layout.clone()).unwrap();
+    assert_eq!(grid.layout(), &layout);
+    assert_eq!(grid.to_string(), "1      2\n3      4\n");
+
+    assert!(Grid::with_layout(vec!["1", "2", "3"], options(), layout.clone()).is_none());
+    assert!(Grid::with_layout(vec!["1", "2", "3", "wider"], options(), layout).is_none());
+}
+
+#[cfg(all(feature = "resize", unix))]
+#[test]
+fn rerender_on_resize() {
+    use std::ops::ControlFlow;
+
+    let mut grid = Grid::new(
+        vec!["one", "two", "three"],
         GridOptions {
-            direction: Direction::TopToBottom,
-            filling: Filling::Spaces(2),
-            width: 4,
+            direction: Direction::LeftToRight,
+            filling: Filling::Spaces(1),
+            width: Width::Columns(80),
+            ..Default::default()
         },
     );
 
-    assert_eq!(grid.row_count(), 2);
+    let mut renders = Vec::new();
+    grid.watch_resize(|output| {
+        renders.push(output.to_string());
+        if renders.len() == 1 {
+            signal_hook::low_level::raise(signal_hook::consts::SIGWINCH).unwrap();
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    })
+    .unwrap();
+
+    assert_eq!(renders.len(), 2);
 }
 
-// This is a reproduction of https://github.com/eza-community/eza/issues/845
 #[test]
-fn eza_many_folders() {
-    let cells: Vec<_> = (100000i32..=100401).map(|i| i.to_string()).collect();
+fn auto_width() {
+    use std::io::IsTerminal;
+    use term_grid::DEFAULT_WIDTH;
+
+    // Everything that depends on the environment is checked in this one
+    // test, so that the checks can't interfere with each other. The tests
+    // usually don't run in a terminal, in which case the environment
+    // variable and the default width are used.
+    if std::io::stdout().is_terminal() {
+        return;
+    }
+
+    std::env::set_var("COLUMNS", "30");
+    assert_eq!(Width::Auto.resolve(), 30);
 
+    std::env::set_var("COLUMNS", "zero");
+    assert_eq!(Width::Auto.resolve(), DEFAULT_WIDTH);
+
+    std::env::remove_var("COLUMNS");
+    assert_eq!(Width::Auto.resolve(), DEFAULT_WIDTH);
+
+    #[cfg(feature = "terminal")]
+    {
+        let grid = Grid::new_auto(vec!["a"; 40], Direction::LeftToRight, Filling::Spaces(1));
+        assert_eq!(grid.width(), DEFAULT_WIDTH - 1);
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_layout() {
     let grid = Grid::new(
-        cells,
+        vec!["a", "bb", "c"],
         GridOptions {
             direction: Direction::TopToBottom,
             filling: Filling::Spaces(2),
-            width: 166,
+            width: Width::Columns(5),
+            ..Default::default()
         },
     );
 
-    assert_eq!(grid.row_count(), 20);
+    assert_eq!(
+        grid.to_json(),
+        concat!(
+            r#"{"rows":2,"column_widths":[2,1],"cells":["#,
+            r#"{"index":0,"original_index":0,"row":0,"column":0,"width":1,"contents":"a"},"#,
+            r#"{"index":1,"original_index":1,"row":1,"column":0,"width":2,"contents":"bb"},"#,
+            r#"{"index":2,"original_index":2,"row":0,"column":1,"width":1,"contents":"c"}]}"#,
+        )
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_layout_after_sorting() {
+    let mut grid = Grid::new(
+        vec!["c", "a", "b"],
+        GridOptions {
+            filling: Filling::Spaces(1),
+            width: Width::Columns(80),
+            ..Default::default()
+        },
+    );
+    grid.sort_by(|a, b| a.cmp(b));
+
+    assert_eq!(
+        grid.to_json(),
+        concat!(
+            r#"{"rows":1,"column_widths":[1,1,1],"cells":["#,
+            r#"{"index":0,"original_index":1,"row":0,"column":0,"width":1,"contents":"a"},"#,
+            r#"{"index":1,"original_index":2,"row":0,"column":1,"width":1,"contents":"b"},"#,
+            r#"{"index":2,"original_index":0,"row":0,"column":2,"width":1,"contents":"c"}]}"#,
+        )
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn options_from_config() {
+    let options: GridOptions =
+        serde_json::from_str(r#"{"direction": "left-to-right", "filling": {"text": " | "}}"#)
+            .unwrap();
+    assert_eq!(options.direction, Direction::LeftToRight);
+    assert_eq!(options.filling, Filling::Text(" | ".into()));
+    assert_eq!(options.width, Width::Auto);
+
+    let options = GridOptions {
+        width: Width::Columns(40),
+        ..GridOptions::dense()
+    };
+    let json = serde_json::to_string(&options).unwrap();
+    assert!(json.starts_with(
+        r#"{"direction":"top-to-bottom","filling":{"spaces":1},"width":{"columns":40},"#
+    ));
+
+    let options: GridOptions = serde_json::from_str(&json).unwrap();
+    assert_eq!(options.filling, Filling::Spaces(1));
+    assert_eq!(options.width, Width::Columns(40));
 }
 
 // These test are based on the tests in uutils ls, to ensure we won't break
@@ -269,7 +2484,8 @@ mod uutils_ls {
                 GridOptions {
                     direction: Direction::TopToBottom,
                     filling: Filling::Spaces(2),
-                    width,
+                    width: Width::Columns(width),
+                    ..Default::default()
                 },
             );
             assert_eq!(expected, grid.to_string());
@@ -288,7 +2504,8 @@ mod uutils_ls {
             GridOptions {
                 direction: Direction::LeftToRight,
                 filling: Filling::Spaces(2),
-                width: 30,
+                width: Width::Columns(30),
+                ..Default::default()
             },
         );
 
@@ -310,7 +2527,8 @@ mod uutils_ls {
             GridOptions {
                 direction: Direction::TopToBottom,
                 filling: Filling::Spaces(2),
-                width: 30,
+                width: Width::Columns(30),
+                ..Default::default()
             },
         );
 
@@ -327,10 +2545,791 @@ mod uutils_ls {
             GridOptions {
                 direction: Direction::TopToBottom,
                 filling: Filling::Spaces(2),
-                width: 15,
+                width: Width::Columns(15),
+                ..Default::default()
             },
         );
 
         assert_eq!("a  a-long-name\nb  z\n", grid.to_string());
     }
 }
+
+#[test]
+fn gnu_ls_compat() {
+    // The output of GNU coreutils 9.1 `ls -C -T 0` and `ls -x -T 0` for
+    // these files, at the given widths.
+    let files = [
+        "a",
+        "bb",
+        "ccc",
+        "dddd",
+        "eeeee",
+        "ffffff",
+        "ggggggg",
+        "hhhhhhhh",
+        "iii",
+        "jj",
+        "k",
+        "lllllllllll",
+    ];
+    let one_line = "a  bb  ccc  dddd  eeeee  ffffff  ggggggg  hhhhhhhh  iii  jj  k  lllllllllll\n";
+    let fixtures = [
+        (
+            Direction::TopToBottom,
+            20,
+            "a       ggggggg\nbb      hhhhhhhh\nccc     iii\ndddd    jj\neeeee   k\nffffff  lllllllllll\n",
+        ),
+        (
+            Direction::TopToBottom,
+            30,
+            "a     eeeee     iii\nbb    ffffff    jj\nccc   ggggggg   k\ndddd  hhhhhhhh  lllllllllll\n",
+        ),
+        (
+            Direction::TopToBottom,
+            36,
+            "a    dddd    ggggggg   jj\nbb   eeeee   hhhhhhhh  k\nccc  ffffff  iii       lllllllllll\n",
+        ),
+        (Direction::TopToBottom, 80, one_line),
+        (
+            Direction::LeftToRight,
+            20,
+            "a\nbb\nccc\ndddd\neeeee\nffffff\nggggggg\nhhhhhhhh\niii\njj\nk\nlllllllllll\n",
+        ),
+        (
+            Direction::LeftToRight,
+            30,
+            "a        bb\nccc      dddd\neeeee    ffffff\nggggggg  hhhhhhhh\niii      jj\nk        lllllllllll\n",
+        ),
+        (
+            Direction::LeftToRight,
+            36,
+            "a      bb      ccc      dddd\neeeee  ffffff  ggggggg  hhhhhhhh\niii    jj      k        lllllllllll\n",
+        ),
+        (Direction::LeftToRight, 80, one_line),
+    ];
+
+    for (direction, width, expected) in fixtures {
+        let grid = Grid::new(
+            files.to_vec(),
+            GridOptions {
+                direction,
+                width: Width::Columns(width),
+                compat: Compat::GnuLs,
+                ..Default::default()
+            },
+        );
+        assert_eq!(grid.to_string(), expected, "{direction:?} at width {width}");
+    }
+
+    // A line exactly as wide as the terminal is too wide for GNU `ls`.
+    let options = |compat| GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(30),
+        compat,
+        ..Default::default()
+    };
+    assert_eq!(
+        Grid::new(files.to_vec(), options(Compat::None))
+            .column_widths()
+            .len(),
+        3
+    );
+    assert_eq!(
+        Grid::new(files.to_vec(), options(Compat::GnuLs))
+            .column_widths()
+            .len(),
+        2
+    );
+
+    // Without the last file, the last row is short, and GNU `ls` ends it
+    // right after its last file, also when padding with tabs.
+    let fixtures = [
+        (
+            Direction::TopToBottom,
+            0,
+            "a    dddd    ggggggg   jj\nbb   eeeee   hhhhhhhh  k\nccc  ffffff  iii\n",
+        ),
+        (
+            Direction::LeftToRight,
+            0,
+            "a      bb      ccc      dddd\neeeee  ffffff  ggggggg  hhhhhhhh\niii    jj      k\n",
+        ),
+        (
+            Direction::TopToBottom,
+            4,
+            "a\t dddd\t ggggggg   jj\nbb\t eeeee\t hhhhhhhh  k\nccc  ffffff  iii\n",
+        ),
+        (
+            Direction::LeftToRight,
+            8,
+            "a      bb      ccc\tdddd\neeeee  ffffff  ggggggg\thhhhhhhh\niii    jj      k\n",
+        ),
+    ];
+    for (direction, tab_size, expected) in fixtures {
+        let options = || GridOptions {
+            direction,
+            filling: Filling::Tabs {
+                spaces: 2,
+                tab_size,
+                stops: Vec::new(),
+            },
+            width: Width::Columns(36),
+            compat: Compat::GnuLs,
+            ..Default::default()
+        };
+        let grid = Grid::new(files[..11].to_vec(), options());
+        assert_eq!(
+            grid.to_string(),
+            expected,
+            "{direction:?} with tab size {tab_size}"
+        );
+
+        if direction == Direction::LeftToRight {
+            let widths = grid.column_widths().to_vec();
+            let mut stream = GridStream::new(Vec::new(), options(), widths);
+            for file in &files[..11] {
+                stream.write_cell(file).unwrap();
+            }
+            let out = stream.finish().unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+    }
+}
+
+#[test]
+fn bsd_ls_compat() {
+    let files = [
+        "a",
+        "bb",
+        "ccc",
+        "dddd",
+        "eeeee",
+        "ffffff",
+        "ggggggg",
+        "hhhhhhhh",
+        "iii",
+        "jj",
+        "k",
+        "lllllllllll",
+    ];
+    let options = |direction, width| GridOptions {
+        direction,
+        filling: Filling::Spaces(1),
+        width: Width::Columns(width),
+        compat: Compat::BsdLs,
+        ..Default::default()
+    };
+
+    // Columns are 16 wide, and five of them fit into 80 columns.
+    let grid = Grid::new(files.to_vec(), options(Direction::TopToBottom, 80));
+    assert_eq!(grid.column_widths(), &[15, 15, 15, 15]);
+    assert_eq!(
+        grid.to_string(),
+        "a               dddd            ggggggg         jj\n\
+         bb              eeeee           hhhhhhhh        k\n\
+         ccc             ffffff          iii             lllllllllll\n"
+    );
+
+    let grid = Grid::new(files.to_vec(), options(Direction::LeftToRight, 80));
+    assert_eq!(
+        grid.lines().next().unwrap(),
+        "a               bb              ccc             dddd            eeeee"
+    );
+    assert_eq!(grid.lines().count(), 3);
+
+    // The last column has to fit in full, even though its cells don't need
+    // all of it.
+    let grid = Grid::new(files.to_vec(), options(Direction::TopToBottom, 47));
+    assert_eq!(grid.column_widths().len(), 2);
+    let grid = Grid::new(files.to_vec(), options(Direction::TopToBottom, 15));
+    assert_eq!(grid.column_widths().len(), 1);
+}
+
+#[test]
+fn tab_padding() {
+    // The output of GNU coreutils 9.1 `ls -C -w 36` and `ls -x -w 36` for
+    // these files, with the given tab sizes.
+    let files = [
+        "a",
+        "bb",
+        "ccc",
+        "dddd",
+        "eeeee",
+        "ffffff",
+        "ggggggg",
+        "hhhhhhhh",
+        "iii",
+        "jj",
+        "k",
+        "lllllllllll",
+    ];
+    let fixtures = [
+        (
+            Direction::TopToBottom,
+            8,
+            "a    dddd    ggggggg   jj\nbb   eeeee   hhhhhhhh  k\nccc  ffffff  iii       lllllllllll\n",
+        ),
+        (
+            Direction::LeftToRight,
+            8,
+            "a      bb      ccc\tdddd\neeeee  ffffff  ggggggg\thhhhhhhh\niii    jj      k\tlllllllllll\n",
+        ),
+        (
+            Direction::TopToBottom,
+            4,
+            "a\t dddd\t ggggggg   jj\nbb\t eeeee\t hhhhhhhh  k\nccc  ffffff  iii\t   lllllllllll\n",
+        ),
+        (
+            Direction::LeftToRight,
+            4,
+            "a\t   bb\t   ccc\t\tdddd\neeeee  ffffff  ggggggg\thhhhhhhh\niii    jj\t   k\t\tlllllllllll\n",
+        ),
+        (
+            Direction::TopToBottom,
+            1,
+            "a\t\t\t dddd\t\t\t ggggggg\t\t jj\nbb\t\t eeeee\t\t hhhhhhhh\t k\nccc\t ffffff\t iii\t\t\t\t\t\t lllllllllll\n",
+        ),
+        (
+            Direction::LeftToRight,
+            1,
+            "a\t\t\t\t\t bb\t\t\t\t\t ccc\t\t\t\t\t dddd\neeeee\t ffffff\t ggggggg\t hhhhhhhh\niii\t\t\t jj\t\t\t\t\t k\t\t\t\t\t\t\t lllllllllll\n",
+        ),
+    ];
+
+    let options = |direction, tab_size| GridOptions {
+        direction,
+        filling: Filling::Tabs {
+            spaces: 2,
+            tab_size,
+            stops: Vec::new(),
+        },
+        width: Width::Columns(36),
+        compat: Compat::GnuLs,
+        ..Default::default()
+    };
+    for (direction, tab_size, expected) in fixtures {
+        let grid = Grid::new(files.to_vec(), options(direction, tab_size));
+        assert_eq!(
+            grid.to_string(),
+            expected,
+            "{direction:?} with tab size {tab_size}"
+        );
+    }
+
+    // A tab size of 0 means spaces only.
+    let grid = Grid::new(files.to_vec(), options(Direction::LeftToRight, 0));
+    let spaces = Grid::new(
+        files.to_vec(),
+        GridOptions {
+            filling: Filling::Spaces(2),
+            ..options(Direction::LeftToRight, 0)
+        },
+    );
+    assert_eq!(grid.to_string(), spaces.to_string());
+}
+
+#[test]
+fn irregular_tab_stops() {
+    let cells = ["a", "bb", "ccc", "dddddd", "e"];
+    let options = || GridOptions {
+        direction: Direction::LeftToRight,
+        filling: Filling::Tabs {
+            spaces: 2,
+            tab_size: 10,
+            stops: vec![3, 6, 12],
+        },
+        width: Width::Columns(80),
+        ..Default::default()
+    };
+
+    // The columns start at 3, 7, 12 and 20. Tabs reach the stops at 3 and
+    // 12. The stop at 6 is only one column after "bb", which is padded with
+    // a space instead, like GNU ls does. Past the last stop, the stops are
+    // every 10 columns, so there is one at 20.
+    let grid = Grid::new(cells.to_vec(), options());
+    assert_eq!(grid.to_string(), "a\tbb  ccc\tdddddd\te\n");
+    assert_eq!(grid.row_width(0), Some(21));
+
+    let widths = grid.column_widths().to_vec();
+    let mut stream = GridStream::new(Vec::new(), options(), widths);
+    for cell in cells {
+        stream.write_cell(&cell).unwrap();
+    }
+    let out = stream.finish().unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), grid.to_string());
+
+    // Stops that are not in increasing order are skipped over.
+    let unsorted = Grid::new(
+        cells.to_vec(),
+        GridOptions {
+            filling: Filling::Tabs {
+                spaces: 2,
+                tab_size: 10,
+                stops: vec![3, 12, 6],
+            },
+            ..options()
+        },
+    );
+    assert_eq!(unsorted.to_string(), grid.to_string());
+}
+
+#[test]
+fn layout_strategies() {
+    let options = |strategy, width| GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(width),
+        strategy,
+        ..Default::default()
+    };
+
+    // Both three and four columns take two rows, but four are narrower.
+    let cells = vec!["wwwwwwwww", "n", "wwwwwwwww", "n", "wwwwwwwww"];
+    let grid = Grid::new(cells.clone(), options(Strategy::MinimizeRows, 31));
+    assert_eq!(grid.column_widths(), &[9, 9, 9]);
+    let grid = Grid::new(cells, options(Strategy::MinimizeWidth, 31));
+    assert_eq!(grid.column_widths(), &[9, 1, 9, 1]);
+    assert_eq!(
+        grid.to_string(),
+        "wwwwwwwww  n  wwwwwwwww  n\nwwwwwwwww  \n"
+    );
+
+    // Twenty cells fit on one row, but four rows of five are squarer.
+    let cells: Vec<String> = ('a'..='t').map(String::from).collect();
+    let grid = Grid::new(cells.clone(), options(Strategy::MinimizeRows, 80));
+    assert_eq!(grid.row_count(), 1);
+    let grid = Grid::new(cells.clone(), options(Strategy::Balanced, 80));
+    assert_eq!(grid.row_count(), 4);
+    assert_eq!(grid.column_widths().len(), 5);
+    let grid = Grid::new(
+        cells,
+        GridOptions {
+            width: Width::Unlimited,
+            ..options(Strategy::Balanced, 0)
+        },
+    );
+    assert_eq!(grid.row_count(), 4);
+}
+
+#[test]
+fn balanced_columns() {
+    let cells: Vec<String> = ('a'..='g').map(String::from).collect();
+    let options = |direction, balance_columns| GridOptions {
+        direction,
+        width: Width::Columns(9),
+        balance_columns,
+        ..Default::default()
+    };
+
+    let grid = Grid::new(cells.clone(), options(Direction::TopToBottom, false));
+    assert_eq!(grid.to_string(), "a  d  g\nb  e  \nc  f  \n");
+    let grid = Grid::new(cells.clone(), options(Direction::TopToBottom, true));
+    assert_eq!(grid.to_string(), "a  d  f\nb  e  g\nc  \n");
+    let grid = Grid::new(cells.clone(), options(Direction::BottomToTop, true));
+    assert_eq!(grid.to_string(), "c\nb  e  g\na  d  f\n");
+
+    // Only columns are balanced.
+    let grid = Grid::new(cells, options(Direction::LeftToRight, true));
+    assert_eq!(grid.to_string(), "a  b  c\nd  e  f\ng  \n");
+}
+
+#[test]
+fn orphans() {
+    let options = |direction, width, avoid_orphans| GridOptions {
+        direction,
+        width: Width::Columns(width),
+        avoid_orphans,
+        ..Default::default()
+    };
+
+    let cells: Vec<String> = ('a'..='e').map(String::from).collect();
+    let grid = Grid::new(cells.clone(), options(Direction::LeftToRight, 5, false));
+    assert_eq!(grid.to_string(), "a  b\nc  d\ne  \n");
+    let grid = Grid::new(cells.clone(), options(Direction::LeftToRight, 5, true));
+    assert_eq!(grid.to_string(), "a\nb\nc\nd\ne\n");
+
+    // Going down the columns, the last row is no different from the others.
+    let grid = Grid::new(cells, options(Direction::TopToBottom, 5, true));
+    assert_eq!(grid.to_string(), "a  d\nb  e\nc  \n");
+
+    let cells = vec!["wwwwwwwww", "n", "wwwwwwwww", "n", "wwwwwwwww"];
+    let grid = Grid::new(
+        cells,
+        GridOptions {
+            strategy: Strategy::MinimizeWidth,
+            ..options(Direction::LeftToRight, 31, true)
+        },
+    );
+    assert_eq!(grid.column_widths(), &[9, 9, 9]);
+
+    // Neither a column fewer nor a row more helps with 13 cells.
+    let cells: Vec<String> = ('a'..='m').map(String::from).collect();
+    let grid = Grid::new(cells, options(Direction::LeftToRight, 10, true));
+    assert_eq!(
+        grid.to_string(),
+        "a  b  c  d\ne  f  g  h\ni  j  k  l\nm  \n"
+    );
+}
+
+#[test]
+fn prefer_fewer_columns() {
+    let cells: Vec<String> = ('a'..='t').map(String::from).collect();
+    let options = |prefer_fewer_columns| GridOptions {
+        width: Width::Columns(80),
+        strategy: Strategy::Balanced,
+        prefer_fewer_columns,
+        ..Default::default()
+    };
+
+    // Four rows of five and five rows of four are as close to a square.
+    let grid = Grid::new(cells.clone(), options(false));
+    assert_eq!((grid.row_count(), grid.column_widths().len()), (4, 5));
+    let grid = Grid::new(cells, options(true));
+    assert_eq!((grid.row_count(), grid.column_widths().len()), (5, 4));
+}
+
+#[test]
+fn width_percentile() {
+    let mut cells: Vec<String> = (0..19).map(|n| format!("file{n}")).collect();
+    cells.push("an-absurdly-long-file-name-that-goes-on.txt".into());
+    let options = |width_percentile| GridOptions {
+        direction: Direction::TopToBottom,
+        width: Width::Columns(50),
+        width_percentile,
+        ..Default::default()
+    };
+
+    // The single long name would leave room for one column only.
+    let grid = Grid::new(cells.clone(), options(None));
+    assert_eq!(grid.column_widths().len(), 1);
+
+    let grid = Grid::new(cells.clone(), options(Some(95)));
+    assert_eq!(grid.column_widths(), &[5, 5, 6, 6, 6]);
+    assert_eq!(
+        grid.to_string(),
+        concat!(
+            "file0  file4  file8   file12  file16\n",
+            "file1  file5  file9   file13  file17\n",
+            "file2  file6  file10  file14  file18\n",
+            "file3  file7  file11  file15  an-ab…\n",
+        )
+    );
+
+    // Every cell is within the 100th percentile.
+    let grid = Grid::new(cells, options(Some(100)));
+    assert_eq!(grid.column_widths().len(), 1);
+
+    assert_eq!(
+        options(Some(0)).validate(),
+        Err(OptionsError::PercentileOutOfRange(0))
+    );
+    assert_eq!(
+        options(Some(101)).validate(),
+        Err(OptionsError::PercentileOutOfRange(101))
+    );
+}
+
+#[test]
+fn greedy_strategy() {
+    let options = |strategy| GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(20),
+        strategy,
+        ..Default::default()
+    };
+
+    // The widest cell only fits twice, even though the others would fit
+    // more often.
+    let cells = vec!["wwwwww", "a", "b", "c", "d", "e"];
+    let grid = Grid::new(cells.clone(), options(Strategy::Greedy));
+    assert_eq!(grid.column_widths(), &[6, 1]);
+    assert_eq!(grid.to_string(), "wwwwww  a\nb       c\nd       e\n");
+    let grid = Grid::new(cells, options(Strategy::MinimizeRows));
+    assert_eq!(grid.to_string(), "wwwwww  a  b\nc       d  e\n");
+
+    // Lots of cells of the same width come out the same either way.
+    let cells: Vec<String> = (0..100_000).map(|i| format!("{:05}", i % 1000)).collect();
+    let greedy = Grid::new(cells.clone(), options(Strategy::Greedy));
+    let search = Grid::new(cells, options(Strategy::MinimizeRows));
+    assert_eq!(greedy.column_widths(), search.column_widths());
+    assert_eq!(greedy.row_count(), 33_334);
+}
+
+#[test]
+fn fewest_lines_search() {
+    // Compare the search against trying every number of lines, going down
+    // the columns.
+    let mut seed = 7_u32;
+    let cells: Vec<String> = (0..300)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            "x".repeat(1 + (seed >> 16) as usize % 14)
+        })
+        .collect();
+
+    for width in [16, 25, 40, 63, 80, 120, 200] {
+        let fits = |num_lines: usize| {
+            let column_widths: Vec<usize> = cells
+                .chunks(num_lines)
+                .map(|column| column.iter().map(String::len).max().unwrap())
+                .collect();
+            column_widths.iter().sum::<usize>() + 2 * (column_widths.len() - 1) <= width
+        };
+        let expected = (1..=cells.len())
+            .rev()
+            .take_while(|&num_lines| fits(num_lines))
+            .last()
+            .unwrap();
+
+        let grid = Grid::new(
+            cells.clone(),
+            GridOptions {
+                width: Width::Columns(width),
+                ..Default::default()
+            },
+        );
+        assert_eq!(grid.row_count(), expected, "width {width}");
+
+        let reversed = Grid::new(
+            cells.iter().rev().cloned().collect(),
+            GridOptions {
+                width: Width::Columns(width),
+                reverse: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(reversed.column_widths(), grid.column_widths());
+    }
+
+    // Two lines would fit, but three don't, so the search stops at four.
+    let grid = Grid::new(
+        vec!["a", "b", "cccccccc", "dddddddd", "e"],
+        GridOptions {
+            width: Width::Columns(15),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.row_count(), 4);
+    assert_eq!(grid.column_widths(), &[8, 1]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_measurement() {
+    let cells: Vec<String> = (0..50_000)
+        .map(|i| format!("\x1b[1m{}\x1b[0m", "x".repeat(i % 17)))
+        .collect();
+    let options = || GridOptions {
+        width: Width::Columns(120),
+        ..Default::default()
+    };
+
+    let widths = (0..50_000).map(|i| i % 17).collect();
+    let parallel = Grid::new(cells.clone(), options());
+    let serial = Grid::new_with_widths(cells, widths, options());
+    assert_eq!(parallel.column_widths(), serial.column_widths());
+    assert_eq!(parallel.to_string(), serial.to_string());
+
+    // The cells don't need to be `Sync`.
+    let cells: Vec<std::rc::Rc<str>> = (0..10_000).map(|i| i.to_string().into()).collect();
+    let grid = Grid::new(cells, options());
+    assert_eq!(grid.widest_cell(), Some((1000, 4)));
+}
+
+#[test]
+fn very_wide_cells() {
+    let options = || GridOptions {
+        width: Width::Columns(80),
+        ..Default::default()
+    };
+
+    let grid = Grid::new_with_widths(vec!["a", "b"], vec![1, 70_000], options());
+    assert_eq!(grid.column_widths(), &[70_000]);
+
+    let mut grid = Grid::new(vec!["a".to_string(), "b".to_string()], options());
+    assert_eq!(grid.column_widths(), &[1, 1]);
+    grid.set(1, "x".repeat(70_000));
+    assert_eq!(grid.column_widths(), &[70_000]);
+    grid.push("c".into());
+    grid.remove(1);
+    assert_eq!(grid.to_string(), "a  c\n");
+}
+
+#[cfg(feature = "ratatui")]
+#[test]
+fn ratatui_widget() {
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::style::{Color, Style};
+    use ratatui::widgets::{StatefulWidget, Widget};
+    use term_grid::{GridWidget, GridWidgetState};
+
+    let grid = Grid::new(
+        vec![
+            "one",
+            "\x1b[1mtwo\x1b[0m",
+            "three",
+            "four",
+            "five",
+            "six",
+            "seven",
+        ],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(18),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        grid.to_string(),
+        "one    \x1b[1mtwo\x1b[0m   three\nfour   five  six\nseven  \n"
+    );
+
+    // The area is narrower and lower than the grid, and doesn't start at
+    // the corner of the buffer. Nothing is written outside of it.
+    let mut buf = Buffer::empty(Rect::new(0, 0, 16, 4));
+    let area = Rect::new(2, 1, 12, 2);
+    Widget::render(GridWidget::new(&grid), area, &mut buf);
+    assert_eq!(
+        buf,
+        Buffer::with_lines([
+            "                ",
+            "  one    two    ",
+            "  four   five   ",
+            "                ",
+        ])
+    );
+
+    // As a stateful widget, it starts at the offset.
+    let mut buf = Buffer::empty(Rect::new(0, 0, 18, 3));
+    let mut state = GridWidgetState { offset: 1 };
+    let style = Style::default().fg(Color::Green);
+    let widget = GridWidget::new(&grid).style(style);
+    StatefulWidget::render(widget, buf.area, &mut buf, &mut state);
+    let mut expected = Buffer::with_lines(["four   five  six  ", "seven", ""]);
+    expected.set_style(expected.area, style);
+    assert_eq!(buf, expected);
+
+    // Tabs are expanded to the tab stops.
+    let grid = Grid::new(
+        vec!["a", "bb", "ccc", "dddddd", "e"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            filling: Filling::Tabs {
+                spaces: 2,
+                tab_size: 10,
+                stops: vec![3, 6, 12],
+            },
+            width: Width::Columns(30),
+            ..Default::default()
+        },
+    );
+    assert_eq!(grid.to_string(), "a\tbb  ccc\tdddddd\te\n");
+    let mut buf = Buffer::empty(Rect::new(0, 0, 24, 1));
+    Widget::render(GridWidget::new(&grid), buf.area, &mut buf);
+    assert_eq!(buf, Buffer::with_lines(["a  bb  ccc  dddddd  e   "]));
+
+    // A row that wraps onto several lines takes up as many lines of the
+    // area, and is cut off at the bottom like the rows are.
+    let grid = Grid::new(
+        vec!["a", "a rather long cell", "c"],
+        GridOptions {
+            direction: Direction::TopToBottom,
+            width: Width::Columns(10),
+            overflow: OverflowPolicy::Wrap,
+            ..Default::default()
+        },
+    );
+    let mut buf = Buffer::empty(Rect::new(0, 0, 10, 2));
+    let mut state = GridWidgetState { offset: 1 };
+    StatefulWidget::render(GridWidget::new(&grid), buf.area, &mut buf, &mut state);
+    assert_eq!(buf, Buffer::with_lines(["a rather l", "ong cell  "]));
+}
+
+#[cfg(feature = "crossterm")]
+#[test]
+fn crossterm_commands() {
+    use crossterm::style::{Color, ContentStyle, Stylize};
+
+    let grid = Grid::new(
+        vec!["one", "\x1b[1mtwo\x1b[0m", "three", "four", "five"],
+        GridOptions {
+            direction: Direction::LeftToRight,
+            width: Width::Columns(18),
+            ..Default::default()
+        },
+    );
+    let style = |index: usize, _: &str| match index {
+        1 => ContentStyle::new().bold(),
+        3 => ContentStyle::new().with(Color::Red),
+        _ => ContentStyle::new(),
+    };
+
+    // Only the contents of the cells are styled, and the escape sequences
+    // that were in them are left out.
+    let mut out = Vec::new();
+    grid.write_crossterm(&mut out, style).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "one   \x1b[1mtwo\x1b[0m   three\n\x1b[38;5;9mfour\x1b[39m  five  \n"
+    );
+
+    // Errors from the writer are passed on.
+    struct Full;
+    impl std::io::Write for Full {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::WriteZero.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let error = grid.write_crossterm(&mut Full, style).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::WriteZero);
+}
+
+#[cfg(feature = "termcolor")]
+#[test]
+fn termcolor_write_color() {
+    use termcolor::{Buffer, Color, ColorSpec};
+
+    let cells = vec!["one", "\x1b[1mtwo\x1b[0m", "three", "four", "five"];
+    let options = || GridOptions {
+        direction: Direction::LeftToRight,
+        width: Width::Columns(18),
+        ..Default::default()
+    };
+    let grid = Grid::new(cells.clone(), options());
+    let style = |index: usize, _: &str| {
+        let mut spec = ColorSpec::new();
+        match index {
+            1 => spec.set_bold(true),
+            3 => spec.set_fg(Some(Color::Red)),
+            _ => &mut spec,
+        };
+        spec
+    };
+
+    // Only the contents of the cells are colored, and the escape sequences
+    // that were in them are left out.
+    let mut out = Buffer::ansi();
+    grid.write_color(&mut out, style).unwrap();
+    assert_eq!(
+        String::from_utf8(out.into_inner()).unwrap(),
+        "one   \x1b[0m\x1b[1mtwo\x1b[0m   three\n\x1b[0m\x1b[31mfour\x1b[0m  five  \n"
+    );
+
+    // Without colors, the output is the same as with `strip_ansi`.
+    let stripped = Grid::new(
+        cells,
+        GridOptions {
+            strip_ansi: true,
+            ..options()
+        },
+    );
+    let mut out = Buffer::no_color();
+    grid.write_color(&mut out, style).unwrap();
+    assert_eq!(
+        String::from_utf8(out.into_inner()).unwrap(),
+        stripped.to_string()
+    );
+}